@@ -18,7 +18,7 @@ pub enum ConnectionState {
     #[default]
     Disconnected,
     Connecting,
-    Connected(Connection),
+    Connected(Box<Connection>),
 }
 
 pub struct Connection {
@@ -29,14 +29,15 @@ pub struct Connection {
 }
 
 pub enum RoomState {
-    Connected {
-        joining: bool,
-    },
+    Connected,
     InRoom {
-        room: dto::Room,
+        room: Box<dto::Room>,
         join_requests: Vec<dto::TransactionId>,
         undo_requested: bool,
-        leaving: bool,
+        /// The opponent offered a draw, that hasn't been answered yet.
+        draw_offered: bool,
+        /// The last draw offer was declined.
+        draw_declined: bool,
     },
 }
 
@@ -80,7 +81,7 @@ fn start_websocket(
         let socket = match connection {
             Ok((socket, _resp)) => socket,
             Err(TungsteniteError::ConnectionClosed) => todo!(),
-            Err(e) => todo!(),
+            Err(_e) => todo!(),
         };
 
         let (socket_sender, mut socket_receiver) = socket.split();
@@ -103,17 +104,17 @@ fn start_websocket(
             };
             match serde_json::from_slice(bytes) {
                 Ok(ServerMsg::Welcome(u)) => break u,
-                Ok(m) => todo!(),
+                Ok(_m) => todo!(),
                 Err(_) => todo!(),
             }
         };
 
-        *state.lock().await = ConnectionState::Connected(Connection {
+        *state.lock().await = ConnectionState::Connected(Box::new(Connection {
             user,
             open_rooms: Vec::new(),
             join_allowed: Vec::new(),
-            state: RoomState::Connected { joining: false },
-        });
+            state: RoomState::Connected,
+        }));
 
         let receiver_task = tokio::spawn(receiver_task(
             Arc::clone(&state),
@@ -168,7 +169,7 @@ async fn receiver_task(
                 connection.open_rooms = rooms;
             }
             ServerMsg::JoinRoomRequested(transaction) => match &mut connection.state {
-                RoomState::Connected { .. } => todo!(),
+                RoomState::Connected => todo!(),
                 RoomState::InRoom { join_requests, .. } => {
                     join_requests.push(transaction);
                 }
@@ -180,12 +181,13 @@ async fn receiver_task(
                 connection.join_allowed.retain(|(_, t)| *t != transaction);
             }
             ServerMsg::Sync(room) => match &mut connection.state {
-                RoomState::Connected { .. } => {
+                RoomState::Connected => {
                     connection.state = RoomState::InRoom {
-                        room,
+                        room: Box::new(room),
                         join_requests: Vec::new(),
                         undo_requested: false,
-                        leaving: false,
+                        draw_offered: false,
+                        draw_declined: false,
                     };
                 }
                 RoomState::InRoom {
@@ -193,31 +195,85 @@ async fn receiver_task(
                     undo_requested,
                     ..
                 } => {
-                    *r = room;
+                    **r = room;
                     *undo_requested = false;
                 }
             },
             ServerMsg::SyncEmpty => {
-                connection.state = RoomState::Connected { joining: false };
+                connection.state = RoomState::Connected;
                 session.send(ClientMsg::ListRooms).await.unwrap();
             }
-            ServerMsg::AppliedMove(m) => match &mut connection.state {
-                RoomState::Connected { .. } => todo!(),
+            ServerMsg::AppliedMove(m, _, _) => match &mut connection.state {
+                RoomState::Connected => todo!(),
                 RoomState::InRoom {
                     room,
                     undo_requested,
+                    draw_offered,
+                    draw_declined,
                     ..
                 } => {
                     room.game.submit_move(m);
                     *undo_requested = false;
+                    // the server drops draw offers once a move is made
+                    *draw_offered = false;
+                    *draw_declined = false;
                 }
             },
             ServerMsg::UndoRequested => match &mut connection.state {
-                RoomState::Connected { .. } => todo!(),
+                RoomState::Connected => todo!(),
                 RoomState::InRoom { undo_requested, .. } => {
                     *undo_requested = true;
                 }
             },
+            ServerMsg::UndoneMove(_, delta, turn) => match &mut connection.state {
+                // the room was left in the meantime
+                RoomState::Connected => (),
+                RoomState::InRoom {
+                    room,
+                    undo_requested,
+                    ..
+                } => {
+                    // step back in the history, but the board and turn of the
+                    // server take precedence
+                    room.game.undo_move();
+                    for &(pos, _, after) in delta.changes.iter() {
+                        room.game[pos] = after;
+                    }
+                    room.game.turn = turn;
+                    *undo_requested = false;
+                }
+            },
+            ServerMsg::DrawOffered => match &mut connection.state {
+                RoomState::Connected => (),
+                RoomState::InRoom {
+                    draw_offered,
+                    draw_declined,
+                    ..
+                } => {
+                    *draw_offered = true;
+                    *draw_declined = false;
+                }
+            },
+            ServerMsg::DrawAgreed => match &mut connection.state {
+                RoomState::Connected => (),
+                RoomState::InRoom {
+                    room, draw_offered, ..
+                } => {
+                    room.game.agree_draw();
+                    *draw_offered = false;
+                }
+            },
+            ServerMsg::DrawDeclined => match &mut connection.state {
+                RoomState::Connected => (),
+                RoomState::InRoom {
+                    draw_offered,
+                    draw_declined,
+                    ..
+                } => {
+                    *draw_offered = false;
+                    *draw_declined = true;
+                }
+            },
             ServerMsg::Error(e) => println!("Error: {e}"),
         }
     }
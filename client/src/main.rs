@@ -1,13 +1,19 @@
 use std::f32::consts::{FRAC_PI_4, FRAC_PI_6, PI, TAU};
+use std::fmt::Write as _;
 use std::sync::Arc;
 
 use abalone::dto::ClientMsg;
-use abalone_core::{self as abalone, Abalone, Color, Dir, SelectionError};
+use abalone::script::ScriptError;
+use abalone_core::{
+    self as abalone, Abalone, Color, Dir, DrawReason, ExtendError, Outcome, SelectionError,
+    StartPosition,
+};
 use async_channel::{Receiver, Sender};
 use eframe::{CreationContext, NativeOptions};
 use egui::{
     Align2, CentralPanel, Color32, FontFamily, FontId, Frame, Id, InputState, Key, Modifiers,
-    Painter, Pos2, Rect, Response, Rounding, ScrollArea, Sense, Stroke, TextEdit, Ui, Vec2,
+    Painter, Pos2, Rect, Response, Rounding, ScrollArea, Sense, Slider, Stroke, TextEdit, Ui, Vec2,
+    Window,
 };
 use egui_extras::{Size, StripBuilder};
 use serde_derive::{Deserialize, Serialize};
@@ -29,6 +35,25 @@ const ERROR_COLOR: Color32 = Color32::from_rgb(0xE0, 0x60, 0x40);
 
 const ERROR_DISPLAY_TIME: f64 = 0.4;
 
+/// Drag thresholds are relative to the distance between two balls.
+const DEFAULT_DRAG_THRESHOLD: f32 = 0.5;
+const MIN_DRAG_THRESHOLD: f32 = 0.1;
+const MAX_DRAG_THRESHOLD: f32 = 1.5;
+
+/// Animation speeds are relative to the default durations.
+const DEFAULT_ANIMATION_SPEED: f32 = 1.0;
+const MIN_ANIMATION_SPEED: f32 = 0.25;
+const MAX_ANIMATION_SPEED: f32 = 4.0;
+
+const BOARD_FLIP_TIME: f32 = 0.3;
+
+const START_POSITIONS: [(StartPosition, &str); 4] = [
+    (StartPosition::Standard, "Standard"),
+    (StartPosition::BelgianDaisy, "Belgian daisy"),
+    (StartPosition::GermanDaisy, "German daisy"),
+    (StartPosition::Snakes, "Snakes"),
+];
+
 fn main() {
     let native_options = NativeOptions {
         follow_system_theme: true,
@@ -45,6 +70,10 @@ fn main() {
 #[derive(Default, Serialize, Deserialize)]
 struct AbaloneApp {
     userdata: Userdata,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(skip)]
+    settings_open: bool,
     state: State,
 }
 
@@ -66,13 +95,61 @@ struct Userdata {
     username: String,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    /// The length a drag has to exceed to be interpreted as a direction,
+    /// relative to the distance between two balls.
+    drag_threshold: f32,
+    /// Show the coordinates of the hovered cell next to the pointer.
+    show_coordinates: bool,
+    /// Multiplier of the default animation speed.
+    animation_speed: f32,
+    /// Disable all animations.
+    reduce_motion: bool,
+    /// Mark the balls the player on turn could lose or push off next.
+    show_threats: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            drag_threshold: DEFAULT_DRAG_THRESHOLD,
+            show_coordinates: false,
+            animation_speed: DEFAULT_ANIMATION_SPEED,
+            reduce_motion: false,
+            show_threats: false,
+        }
+    }
+}
+
+impl Settings {
+    fn drag_threshold(&self) -> f32 {
+        self.drag_threshold
+            .clamp(MIN_DRAG_THRESHOLD, MAX_DRAG_THRESHOLD)
+    }
+
+    /// The duration of an animation that takes `time` seconds at the default
+    /// speed. With reduced motion animations finish instantly, even if they
+    /// were already in progress.
+    fn animation_time(&self, time: f32) -> f32 {
+        if self.reduce_motion {
+            return 0.0;
+        }
+        let speed = self
+            .animation_speed
+            .clamp(MIN_ANIMATION_SPEED, MAX_ANIMATION_SPEED);
+        time / speed
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 enum State {
     #[default]
     Home,
     #[serde(skip)]
     Online(OnlineGame),
-    Offline(OfflineGame),
+    Offline(Box<OfflineGame>),
 }
 
 enum Navigation {
@@ -101,23 +178,84 @@ impl OnlineGame {
 #[derive(Default, Serialize, Deserialize)]
 struct OfflineGame {
     game: Abalone,
+    /// The real game while moves are explored on a copy of it in
+    /// [`Self::game`], see [`OfflineGame::enter_analysis`].
+    #[serde(default)]
+    live_game: Option<Abalone>,
+    #[serde(default)]
+    start: StartPosition,
     #[serde(skip)]
     drag: Option<(DragKind, Pos2, Pos2)>,
     #[serde(skip)]
     selection: SelectionState,
     #[serde(skip)]
     input_errors: Vec<InputError>,
+    #[serde(skip)]
+    menu_open: bool,
+    #[serde(skip)]
+    script: String,
+    #[serde(skip)]
+    script_error: Option<ScriptError>,
+    #[serde(skip)]
+    threats: Option<Threats>,
     board_flipped: bool,
 }
 
 impl OfflineGame {
-    fn reset(&mut self) {
-        self.game = Abalone::new();
+    fn reset_to(&mut self, start: StartPosition) {
+        self.game = Abalone::with_start_position(start);
+        self.live_game = None;
+        self.start = start;
+        self.drag = None;
+        self.selection = SelectionState::NoSelection;
+        self.input_errors.clear();
+    }
+
+    /// Continues with a copy of the current position, so moves can be
+    /// explored without changing the history of the real game.
+    fn enter_analysis(&mut self) {
+        if self.live_game.is_some() {
+            return;
+        }
+        let scratch = self.game.clone_position();
+        self.live_game = Some(std::mem::replace(&mut self.game, scratch));
+        self.drag = None;
+        self.selection = SelectionState::NoSelection;
+        self.input_errors.clear();
+    }
+
+    /// Discards the explored moves and returns to the real game.
+    fn leave_analysis(&mut self) {
+        let Some(game) = self.live_game.take() else {
+            return;
+        };
+        self.game = game;
+        self.drag = None;
         self.selection = SelectionState::NoSelection;
         self.input_errors.clear();
     }
 }
 
+/// The balls that could be pushed off with the next move, computed for the
+/// position with the key.
+struct Threats {
+    position_key: u64,
+    /// Balls of the player on turn.
+    threatened: Vec<abalone::Pos2>,
+    /// Balls of the opponent.
+    capturable: Vec<abalone::Pos2>,
+}
+
+impl Threats {
+    fn new(game: &Abalone) -> Self {
+        Self {
+            position_key: game.position_key(),
+            threatened: game.threatened(game.turn),
+            capturable: game.threatened(game.turn.opposite()),
+        }
+    }
+}
+
 enum InputError {
     WrongTurn {
         start_secs: f64,
@@ -131,6 +269,7 @@ enum InputError {
     CantExtendSelection {
         start_secs: f64,
         pos: abalone::Pos2,
+        reason: ExtendError,
     },
 }
 
@@ -156,6 +295,7 @@ struct Dimensions {
     line_thickness: f32,
     selection_radius: f32,
     board_angle: f32,
+    drag_threshold: f32,
 }
 
 impl eframe::App for AbaloneApp {
@@ -164,13 +304,21 @@ impl eframe::App for AbaloneApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        ctx.input_mut(|i| {
+            if i.consume_key(Modifiers::COMMAND, Key::Comma) {
+                self.settings_open = !self.settings_open;
+            } else if self.settings_open && i.consume_key(Modifiers::NONE, Key::Escape) {
+                self.settings_open = false;
+            }
+        });
+
         CentralPanel::default()
             .frame(Frame::none().fill(Color32::from_gray(0x2B)))
             .show(ctx, |ui| {
                 let nav = match &mut self.state {
                     State::Home => draw_home(ui, self),
                     State::Online(g) => draw_online_game(ui, &mut self.userdata, g),
-                    State::Offline(g) => draw_game(ui, g),
+                    State::Offline(g) => draw_game(ui, &self.settings, &mut self.settings_open, g),
                 };
 
                 match nav {
@@ -178,13 +326,15 @@ impl eframe::App for AbaloneApp {
                     Navigation::Stay => (),
                 }
             });
+
+        draw_settings(ctx, &mut self.settings, &mut self.settings_open);
     }
 }
 
 fn draw_home(ui: &mut Ui, app: &mut AbaloneApp) -> Navigation {
     ui.vertical_centered_justified(|ui| {
         if ui.button("Offline game").clicked() {
-            app.state = State::Offline(OfflineGame::default());
+            app.state = State::Offline(Box::default());
         }
     });
     ui.vertical_centered_justified(|ui| {
@@ -192,12 +342,52 @@ fn draw_home(ui: &mut Ui, app: &mut AbaloneApp) -> Navigation {
             app.state = State::Online(OnlineGame::new());
         }
     });
+    ui.vertical_centered_justified(|ui| {
+        if ui.button("\u{2699} Settings").clicked() {
+            app.settings_open = true;
+        }
+    });
 
     Navigation::Stay
 }
 
+/// The window grouping all settings, toggled with `Ctrl+,` and closed with
+/// `Escape`. The settings are persisted along with the rest of the app.
+fn draw_settings(ctx: &egui::Context, settings: &mut Settings, open: &mut bool) {
+    Window::new("Settings")
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label("Input");
+            let range = MIN_DRAG_THRESHOLD..=MAX_DRAG_THRESHOLD;
+            let slider = Slider::new(&mut settings.drag_threshold, range).text("Drag threshold");
+            ui.add(slider);
+            ui.separator();
+
+            ui.label("Display");
+            ui.checkbox(&mut settings.show_coordinates, "Show coordinates");
+            ui.checkbox(&mut settings.show_threats, "Show threats");
+            ui.separator();
+
+            ui.label("Animations");
+            let range = MIN_ANIMATION_SPEED..=MAX_ANIMATION_SPEED;
+            let slider = Slider::new(&mut settings.animation_speed, range)
+                .text("Animation speed")
+                .logarithmic(true);
+            ui.add_enabled(!settings.reduce_motion, slider);
+            ui.checkbox(&mut settings.reduce_motion, "Reduce motion");
+            ui.separator();
+
+            if ui.button("Reset to defaults").clicked() {
+                *settings = Settings::default();
+            }
+        });
+}
+
 fn draw_online_game(ui: &mut Ui, userdata: &mut Userdata, app: &mut OnlineGame) -> Navigation {
-    let mut nav = Navigation::Stay;
+    let nav = Navigation::Stay;
 
     let mut state_lock = app.state.blocking_lock();
     match &mut *state_lock {
@@ -225,7 +415,7 @@ fn draw_online_game(ui: &mut Ui, userdata: &mut Userdata, app: &mut OnlineGame)
             ui.label(connection.user.name.to_string());
 
             match &mut connection.state {
-                connection::RoomState::Connected { joining } => {
+                connection::RoomState::Connected => {
                     if ui.button("refresh").clicked() {
                         let msg = ClientMsg::ListRooms;
                         app.sender.send_blocking(msg).unwrap();
@@ -290,11 +480,38 @@ fn draw_online_game(ui: &mut Ui, userdata: &mut Userdata, app: &mut OnlineGame)
                 }
                 connection::RoomState::InRoom {
                     room,
-                    join_requests,
-                    undo_requested,
-                    leaving,
+                    draw_offered,
+                    draw_declined,
+                    ..
                 } => {
-                    todo!();
+                    ui.heading(&room.name);
+
+                    match room.game.outcome() {
+                        Some(outcome) => {
+                            ui.label(outcome_text(outcome));
+                        }
+                        None if *draw_offered => {
+                            ui.label("Your opponent offers a draw");
+                            ui.horizontal(|ui| {
+                                for (text, accept) in [("Accept", true), ("Decline", false)] {
+                                    if ui.button(text).clicked() {
+                                        let msg = ClientMsg::AnswerDraw { accept };
+                                        app.sender.send_blocking(msg).unwrap();
+                                        *draw_offered = false;
+                                    }
+                                }
+                            });
+                        }
+                        None => {
+                            if *draw_declined {
+                                ui.label("The draw offer was declined");
+                            }
+                            if ui.button("Offer draw").clicked() {
+                                app.sender.send_blocking(ClientMsg::OfferDraw).unwrap();
+                                *draw_declined = false;
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -303,11 +520,26 @@ fn draw_online_game(ui: &mut Ui, userdata: &mut Userdata, app: &mut OnlineGame)
     nav
 }
 
-fn draw_game(ui: &mut Ui, app: &mut OfflineGame) -> Navigation {
+/// The result of a finished game, as shown to the players.
+fn outcome_text(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Win(winner) => format!("The game is over, {winner} has won"),
+        Outcome::Draw(DrawReason::Agreement) => "The game was drawn by agreement".to_string(),
+        Outcome::Draw(DrawReason::MoveLimit) => "The game was drawn by the move limit".to_string(),
+    }
+}
+
+fn draw_game(
+    ui: &mut Ui,
+    settings: &Settings,
+    settings_open: &mut bool,
+    app: &mut OfflineGame,
+) -> Navigation {
     // TODO: fix animation snapping when changing direction while animation is still in progress.
+    let flip_time = settings.animation_time(BOARD_FLIP_TIME);
     let board_angle = PI
         * ui.ctx()
-            .animate_bool_with_time(Id::new("board_angle"), app.board_flipped, 0.3);
+            .animate_bool_with_time(Id::new("board_angle"), app.board_flipped, flip_time);
 
     let screen_size = ui.available_size();
     let center = (0.5 * screen_size).to_pos2() + Vec2::new(0.0, 0.05 * screen_size.min_elem());
@@ -316,6 +548,7 @@ fn draw_game(ui: &mut Ui, app: &mut OfflineGame) -> Navigation {
     let ball_radius = 0.4 * ball_offset;
     let line_thickness = 0.1 * ball_radius;
     let selection_radius = ball_radius - 0.5 * line_thickness;
+    let drag_threshold = settings.drag_threshold() * ball_offset;
     let dim = Dimensions {
         screen_size,
         center,
@@ -324,16 +557,53 @@ fn draw_game(ui: &mut Ui, app: &mut OfflineGame) -> Navigation {
         line_thickness,
         selection_radius,
         board_angle,
+        drag_threshold,
     };
 
     ui.input_mut(|i| {
-        check_input(i, app, &dim);
+        check_input(i, app, *settings_open, &dim);
     });
 
-    draw_board(ui, app, &dim)
+    let nav = draw_board(ui, settings, settings_open, app, &dim);
+
+    if settings.show_coordinates && !app.menu_open && !*settings_open {
+        draw_coordinates(ui, &dim);
+    }
+
+    nav
 }
 
-fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigation {
+/// Draws the grid position and notation of the hovered cell next to the
+/// pointer, unless a drag is in progress.
+fn draw_coordinates(ui: &Ui, dim: &Dimensions) {
+    let (hover_pos, dragging) =
+        ui.input(|i| (i.pointer.hover_pos(), i.pointer.is_decidedly_dragging()));
+    let Some(hover_pos) = hover_pos else {
+        return;
+    };
+    let pos = screen_to_game_pos(dim, hover_pos);
+    if dragging || !abalone::is_in_bounds(pos) {
+        return;
+    }
+
+    let offset = Vec2::splat(0.2 * dim.ball_offset);
+    let font = FontId::new(0.3 * dim.ball_offset, FontFamily::Proportional);
+    ui.painter().text(
+        hover_pos + offset,
+        Align2::LEFT_TOP,
+        format!("{} {pos}", pos.to_notation()),
+        font,
+        ICON_COLOR,
+    );
+}
+
+fn draw_board(
+    ui: &mut Ui,
+    settings: &Settings,
+    settings_open: &mut bool,
+    app: &mut OfflineGame,
+    dim: &Dimensions,
+) -> Navigation {
     let mut nav = Navigation::Stay;
     let painter = ui.painter();
 
@@ -371,6 +641,25 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
         WHITE_COLOR,
     );
 
+    // push preview
+    let selection = match &app.selection {
+        SelectionState::Selection(selection, None) | SelectionState::Move(selection, _) => {
+            Some(*selection)
+        }
+        _ => None,
+    };
+    if let Some(selection) = selection {
+        let preview_pos = white_score_pos + Vec2::new(0.0, dim.ball_offset + padding);
+        let preview_font = FontId::new(0.3 * dim.ball_offset, FontFamily::Proportional);
+        painter.text(
+            preview_pos,
+            Align2::LEFT_TOP,
+            push_preview(&app.game, selection, app.board_flipped),
+            preview_font,
+            ICON_COLOR,
+        );
+    }
+
     // undo icon
     let undo_pos = used_screen_rect.center_top() + Vec2::new(-2.0 * padding, padding);
     let resp = icon_button(
@@ -400,8 +689,93 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
         "\u{2630}".to_string(),
     );
     if resp.clicked() {
-        // TODO: show proper menu
-        nav = Navigation::Home;
+        app.menu_open = !app.menu_open;
+    }
+    if app.live_game.is_some() {
+        let analysis_pos = used_screen_rect.center_bottom() - Vec2::new(0.0, padding);
+        let analysis_font = FontId::new(0.3 * dim.ball_offset, FontFamily::Proportional);
+        painter.text(
+            analysis_pos,
+            Align2::CENTER_BOTTOM,
+            "Analysis mode",
+            analysis_font,
+            WARN_COLOR,
+        );
+    }
+
+    if app.menu_open {
+        let mut new_game = None;
+        let mut load_script = false;
+        let mut toggle_analysis = false;
+        let mut home = false;
+        let menu_offset = Vec2::new(0.0, padding + 0.6 * dim.ball_offset);
+        Window::new("Menu")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, menu_offset)
+            .show(ui.ctx(), |ui| {
+                ui.label("New game");
+                for (start, name) in START_POSITIONS {
+                    if ui.button(name).clicked() {
+                        new_game = Some(start);
+                    }
+                }
+                ui.separator();
+                ui.label("Replay script");
+                TextEdit::multiline(&mut app.script)
+                    .hint_text("W G5-F4")
+                    .show(ui);
+                if let Some(e) = &app.script_error {
+                    ui.colored_label(ERROR_COLOR, e.to_string());
+                }
+                if ui.button("Load").clicked() {
+                    load_script = true;
+                }
+                ui.separator();
+                let analysis_text = match app.live_game {
+                    Some(_) => "Return to game",
+                    None => "Analysis mode",
+                };
+                if ui.button(analysis_text).clicked() {
+                    toggle_analysis = true;
+                }
+                ui.separator();
+                if ui.button("\u{2699} Settings").clicked() {
+                    *settings_open = true;
+                    app.menu_open = false;
+                }
+                if ui.button("Home").clicked() {
+                    home = true;
+                }
+            });
+
+        if let Some(start) = new_game {
+            app.reset_to(start);
+            app.menu_open = false;
+        }
+        if load_script {
+            let mut game = Abalone::with_start_position(app.start);
+            match game.run_script(&app.script) {
+                Ok(()) => {
+                    app.reset_to(app.start);
+                    app.game = game;
+                    app.script_error = None;
+                    app.menu_open = false;
+                }
+                Err(e) => app.script_error = Some(e),
+            }
+        }
+        if toggle_analysis {
+            match app.live_game {
+                Some(_) => app.leave_analysis(),
+                None => app.enter_analysis(),
+            }
+            app.menu_open = false;
+        }
+        if home {
+            app.menu_open = false;
+            nav = Navigation::Home;
+        }
     }
 
     // redo icon
@@ -437,10 +811,29 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
         }
     }
 
+    // threats are marked inside the balls, so they don't clash with the
+    // highlights around them
+    if settings.show_threats {
+        let key = app.game.position_key();
+        let threats = match &mut app.threats {
+            Some(t) if t.position_key == key => t,
+            threats => threats.insert(Threats::new(&app.game)),
+        };
+        for &p in threats.threatened.iter() {
+            mark_one(painter, dim, p, WARN_COLOR);
+        }
+        for &p in threats.capturable.iter() {
+            mark_one(painter, dim, p, SUCCESS_COLOR);
+        }
+    }
+
     // highlight current state
     match &app.selection {
         SelectionState::NoSelection => (),
         SelectionState::Selection(selection, error) => match error {
+            &Some(SelectionError::OutOfBounds(p)) => {
+                highlight_one(painter, dim, p, ERROR_COLOR);
+            }
             &Some(SelectionError::WrongTurn(p)) => {
                 highlight_one_square(painter, dim, p, ERROR_COLOR);
 
@@ -479,7 +872,12 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
         SelectionState::Move(selection, res) => {
             highlight_selection(painter, dim, *selection, SELECTION_COLOR);
             match res {
-                Err(abalone::Error::Selection(_)) => (),
+                Err(
+                    abalone::Error::Selection(_)
+                    | abalone::Error::GameOver(_)
+                    | abalone::Error::InvalidNotation(_)
+                    | abalone::Error::PassNotAllowed,
+                ) => (),
                 Err(abalone::Error::Move(e)) => match e {
                     abalone::MoveError::PushedOff(pushed_off) => {
                         for &p in pushed_off.iter() {
@@ -516,6 +914,7 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
                         let selection = [first + dir.vec(), last + dir.vec()];
                         highlight_selection(painter, dim, selection, SUCCESS_COLOR)
                     }
+                    abalone::Move::Pass => (),
                 },
             }
         }
@@ -530,8 +929,13 @@ fn draw_board(ui: &mut Ui, app: &mut OfflineGame, dim: &Dimensions) -> Navigatio
                 highlight_one(painter, dim, start, ERROR_COLOR);
                 highlight_one(painter, dim, end, ERROR_COLOR);
             }
-            InputError::CantExtendSelection { pos, .. } => {
+            InputError::CantExtendSelection { pos, reason, .. } => {
                 highlight_one(painter, dim, pos, ERROR_COLOR);
+                let offset = Vec2::new(0.0, dim.ball_offset);
+                let font = FontId::new(0.3 * dim.ball_offset, FontFamily::Proportional);
+                let text = reason.to_string();
+                let text_pos = game_to_screen_pos(dim, pos) + offset;
+                painter.text(text_pos, Align2::CENTER_TOP, text, font, ERROR_COLOR);
             }
         };
     }
@@ -612,6 +1016,12 @@ fn highlight_one(painter: &Painter, dim: &Dimensions, pos: abalone::Pos2, color:
     painter.circle_stroke(pos, dim.selection_radius, stroke);
 }
 
+fn mark_one(painter: &Painter, dim: &Dimensions, pos: abalone::Pos2, color: Color32) {
+    let pos = game_to_screen_pos(dim, pos);
+    painter.circle_filled(pos, 0.3 * dim.ball_radius, with_alpha(color, 0xC0));
+}
+
+#[allow(clippy::too_many_arguments)]
 fn icon_button(
     ui: &Ui,
     painter: &Painter,
@@ -642,13 +1052,24 @@ fn icon_button(
     resp
 }
 
-fn check_input(i: &mut InputState, app: &mut OfflineGame, dim: &Dimensions) {
+fn check_input(i: &mut InputState, app: &mut OfflineGame, settings_open: bool, dim: &Dimensions) {
+    if app.menu_open || settings_open {
+        // the windows receive the keyboard and pointer input
+        if i.consume_key(Modifiers::NONE, Key::Escape) {
+            app.menu_open = false;
+        }
+        app.drag = None;
+        return;
+    }
+
     if i.consume_key(Modifiers::NONE, Key::Space) {
         app.board_flipped = !app.board_flipped;
     } else if i.consume_key(Modifiers::COMMAND, Key::Z) {
         undo(app);
     } else if i.consume_key(Modifiers::COMMAND, Key::Y) {
         redo(app);
+    } else if i.consume_key(Modifiers::COMMAND, Key::N) {
+        app.reset_to(app.start);
     } else if i.consume_key(Modifiers::NONE, Key::Escape) {
         app.selection = SelectionState::NoSelection;
     }
@@ -686,25 +1107,18 @@ fn check_input(i: &mut InputState, app: &mut OfflineGame, dim: &Dimensions) {
                                 let error = app.game.check_selection(selection).err();
                                 app.selection = SelectionState::Selection(selection, error);
                             } else {
-                                let start_vec = pos - start;
-                                let end_vec = pos - end;
-                                if start_vec.is_multiple_of_unit_vec()
-                                    && start_vec.is_parallel(sel_vec)
-                                {
-                                    if start_vec.mag() < end_vec.mag() {
-                                        let selection = [pos, end];
-                                        let error = app.game.check_selection(selection).err();
-                                        app.selection = SelectionState::Selection(selection, error);
-                                    } else {
-                                        let selection = [start, pos];
+                                match abalone::extend_selection([start, end], pos) {
+                                    Ok(selection) => {
                                         let error = app.game.check_selection(selection).err();
                                         app.selection = SelectionState::Selection(selection, error);
                                     }
-                                } else {
-                                    app.input_errors.push(InputError::CantExtendSelection {
-                                        start_secs: i.time,
-                                        pos,
-                                    });
+                                    Err(reason) => {
+                                        app.input_errors.push(InputError::CantExtendSelection {
+                                            start_secs: i.time,
+                                            pos,
+                                            reason,
+                                        });
+                                    }
                                 }
                             }
                         }
@@ -835,27 +1249,51 @@ fn try_move(
     [origin, current]: [Pos2; 2],
 ) -> SelectionState {
     let drag_vec = current - origin;
-    if drag_vec.length() < 0.5 * dim.ball_offset {
+    if drag_vec.length() < dim.drag_threshold {
         let error = game.check_selection(selection).err();
         return SelectionState::Selection(selection, error);
     }
 
     let angle = (6.0 * ((drag_vec.angle() - dim.board_angle + TAU) % TAU) / TAU).round();
-    let idx = (angle as u8) % 6;
-    let dir = match idx {
-        0 => Dir::PosX,
-        1 => Dir::PosZ,
-        2 => Dir::PosY,
-        3 => Dir::NegX,
-        4 => Dir::NegZ,
-        5 => Dir::NegY,
-        _ => unreachable!(),
-    };
+    let dir = Dir::from_sextant(angle as u8);
 
-    let res = game.check_move(selection, dir);
+    let res = game.check_game_move(selection, dir);
     SelectionState::Move(selection, res)
 }
 
+/// Lists how many opposing balls would be pushed in every legal direction of
+/// the selection.
+fn push_preview(game: &Abalone, selection: [abalone::Pos2; 2], board_flipped: bool) -> String {
+    let mut text = String::new();
+    for (dir, mov) in game.legal_dirs(selection) {
+        if !text.is_empty() {
+            text.push_str("  ");
+        }
+        let arrow = dir_arrow(dir, board_flipped);
+        let pushed = game.pushed_count(mov);
+        let off = if let abalone::Move::PushedOff { .. } = mov {
+            " off"
+        } else {
+            ""
+        };
+        _ = write!(text, "{arrow} {pushed}{off}");
+    }
+    text
+}
+
+/// The arrow pointing in the on screen direction of `dir`.
+fn dir_arrow(dir: Dir, board_flipped: bool) -> char {
+    const ARROWS: [char; 6] = [
+        '\u{2192}', '\u{2198}', '\u{2199}', '\u{2190}', '\u{2196}', '\u{2197}',
+    ];
+    let idx = dir.sextant() as usize;
+    if board_flipped {
+        ARROWS[(idx + 3) % 6]
+    } else {
+        ARROWS[idx]
+    }
+}
+
 fn game_to_screen_pos(dim: &Dimensions, pos: abalone::Pos2) -> Pos2 {
     let center_idx = 4;
     let cx = pos.x - center_idx;
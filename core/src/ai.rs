@@ -0,0 +1,194 @@
+//! A simple opponent, searching the legal moves with alpha-beta pruning.
+//!
+//! The positional terms of [`evaluate`] together stay well below
+//! [`MATERIAL_WEIGHT`], so a ball pushed off always outweighs any position.
+
+use crate::{splitmix64, Abalone, Color, Move, Pos2};
+
+/// The score of one opposing ball pushed off the board.
+pub const MATERIAL_WEIGHT: i32 = 1000;
+/// The score of one ball being one cell closer to the center, a ball in the
+/// center is 4 cells closer than one on the edge.
+pub const CENTRALITY_WEIGHT: i32 = 10;
+/// The score of one ball having one more neighbor of its own color, so two
+/// adjacent balls score twice this.
+pub const COHESION_WEIGHT: i32 = 5;
+/// The score of a won position, more than any material balance can reach.
+const WIN_SCORE: i32 = 1_000_000;
+
+/// The distance of the cells on the edge to the center.
+const MAX_CENTER_DISTANCE: i32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// The number of plies searched, at least 1.
+    pub depth: u32,
+    /// Among moves with the same score, choose one pushing off an opposing
+    /// ball over repositioning.
+    pub prefer_push_off: bool,
+    /// Choose randomly among the moves scoring at most this much below the
+    /// best one, so play varies between games. With `0` the first of the best
+    /// moves is chosen.
+    pub epsilon: i32,
+    /// The seed of the random choice, see [`Self::epsilon`].
+    pub seed: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            depth: 3,
+            prefer_push_off: true,
+            epsilon: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// The best move of the current player within `depth` plies, with the default
+/// [`SearchConfig`] otherwise. Returns [`None`] only if there is no legal move.
+pub fn best_move(game: &Abalone, depth: u32) -> Option<Move> {
+    let config = SearchConfig {
+        depth,
+        ..Default::default()
+    };
+    best_move_with(game, &config)
+}
+
+/// The best move of the current player, see [`best_move`]. The search runs on
+/// a copy of the position, so the game is never changed.
+pub fn best_move_with(game: &Abalone, config: &SearchConfig) -> Option<Move> {
+    let mut game = game.clone_position();
+    let depth = config.depth.max(1);
+    let margin = config.epsilon.max(0);
+
+    let mut scored = Vec::new();
+    let mut alpha = -i32::MAX;
+    for mov in ordered_moves(&game, config) {
+        // scores within the margin below the best one have to be exact
+        let floor = alpha
+            .saturating_sub(margin.saturating_add(1))
+            .max(-i32::MAX);
+        play(&mut game, mov);
+        let score = -negamax(&mut game, depth - 1, -i32::MAX, -floor, config);
+        unplay(&mut game, mov);
+
+        alpha = alpha.max(score);
+        scored.push((mov, score));
+    }
+
+    // the first of equally scored moves is kept, so the move order decides
+    // ties
+    let candidates: Vec<Move> = (scored.into_iter())
+        .filter(|&(_, score)| score >= alpha.saturating_sub(margin))
+        .map(|(mov, _)| mov)
+        .collect();
+    let idx = match margin {
+        0 => 0,
+        _ => {
+            let mut state = config.seed;
+            (splitmix64(&mut state) % candidates.len().max(1) as u64) as usize
+        }
+    };
+    candidates.get(idx).copied()
+}
+
+/// The score of the position for the current player after searching `depth`
+/// plies ahead, like [`best_move`] does for each move.
+pub fn score(game: &Abalone, depth: u32) -> i32 {
+    let mut game = game.clone_position();
+    negamax(
+        &mut game,
+        depth,
+        -i32::MAX,
+        i32::MAX,
+        &SearchConfig::default(),
+    )
+}
+
+/// The score of the position for the current player.
+fn negamax(
+    game: &mut Abalone,
+    depth: u32,
+    mut alpha: i32,
+    beta: i32,
+    config: &SearchConfig,
+) -> i32 {
+    if let Some(winner) = game.winner() {
+        // prefer faster wins and slower losses
+        let score = WIN_SCORE + depth as i32;
+        return if winner == game.turn { score } else { -score };
+    }
+    if depth == 0 {
+        return evaluate(game, game.turn);
+    }
+
+    let moves = ordered_moves(game, config);
+    if moves.is_empty() {
+        return evaluate(game, game.turn);
+    }
+
+    let mut best = -i32::MAX;
+    for mov in moves {
+        play(game, mov);
+        let score = -negamax(game, depth - 1, -beta, -alpha, config);
+        unplay(game, mov);
+
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// The legal moves, with moves pushing off a ball first if configured.
+fn ordered_moves(game: &Abalone, config: &SearchConfig) -> Vec<Move> {
+    let mut moves: Vec<Move> = game.legal_moves_iter().map(|(_, _, _, mov)| mov).collect();
+    if config.prefer_push_off {
+        moves.sort_by_key(|mov| !matches!(mov, Move::PushedOff { .. }));
+    }
+    moves
+}
+
+/// Scores the position from the viewpoint of `perspective`, by the balls
+/// pushed off, the proximity of the balls to the center and the number of
+/// neighbors of the same color, weighted by [`MATERIAL_WEIGHT`],
+/// [`CENTRALITY_WEIGHT`] and [`COHESION_WEIGHT`].
+///
+/// Every term is the difference between both colors, so the score of the
+/// opponent is exactly the negation.
+pub fn evaluate(game: &Abalone, perspective: Color) -> i32 {
+    let material = MATERIAL_WEIGHT * game.material_balance(perspective) as i32;
+
+    let center = Pos2 { x: 4, y: 4 };
+    let mut positional = 0;
+    for (x, y, c) in game.iter() {
+        let Some(color) = c else {
+            continue;
+        };
+        let pos = Pos2 { x, y };
+        let closeness = MAX_CENTER_DISTANCE - (pos - center).mag() as i32;
+        let neighbors = (pos.neighbors())
+            .filter(|&p| game[p] == Some(color))
+            .count() as i32;
+        let score = CENTRALITY_WEIGHT * closeness + COHESION_WEIGHT * neighbors;
+        match color == perspective {
+            true => positional += score,
+            false => positional -= score,
+        }
+    }
+
+    material + positional
+}
+
+fn play(game: &mut Abalone, mov: Move) {
+    game.apply_move(mov);
+    game.turn = game.turn.opposite();
+}
+
+fn unplay(game: &mut Abalone, mov: Move) {
+    game.turn = game.turn.opposite();
+    game.unapply_move(mov);
+}
@@ -0,0 +1,79 @@
+//! Positions as bit masks of the balls of each color.
+//!
+//! Bit `y * 9 + x` corresponds to the cell at `(x, y)`, the bits of the cells
+//! outside of the board are never set.
+
+use std::fmt;
+
+use crate::{is_in_bounds, Abalone, Color, Pos2, NUM_STARTING_BALLS, SIZE};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitboardError {
+    /// A cell contains both a black and a white ball.
+    Overlap(Pos2),
+    /// The bit doesn't correspond to a cell of the board.
+    OutOfBounds(u32),
+    /// The color has more than [`NUM_STARTING_BALLS`] balls.
+    TooMany(Color),
+}
+
+impl fmt::Display for BitboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitboardError::Overlap(p) => write!(f, "Both colors at {p}"),
+            BitboardError::OutOfBounds(bit) => write!(f, "Bit {bit} is out of bounds"),
+            BitboardError::TooMany(c) => write!(f, "Too many {c} balls"),
+        }
+    }
+}
+
+impl Abalone {
+    /// The black and white balls as bit masks, see [`crate::bitboard`].
+    pub fn bitboards(&self) -> (u128, u128) {
+        let mut black = 0;
+        let mut white = 0;
+        for (x, y, c) in self.iter() {
+            let bit = 1 << (y as u32 * SIZE as u32 + x as u32);
+            match c {
+                Some(Color::Black) => black |= bit,
+                Some(Color::White) => white |= bit,
+                None => (),
+            }
+        }
+        (black, white)
+    }
+
+    /// Decodes the bit masks returned by [`Self::bitboards`], the game starts
+    /// with an empty history.
+    pub fn from_bitboards(black: u128, white: u128, turn: Color) -> Result<Abalone, BitboardError> {
+        let mut game = Abalone::new();
+        for (_, c) in game.iter_mut() {
+            *c = None;
+        }
+        game.turn = turn;
+
+        for (mask, color) in [(black, Color::Black), (white, Color::White)] {
+            if mask.count_ones() > NUM_STARTING_BALLS as u32 {
+                return Err(BitboardError::TooMany(color));
+            }
+            for bit in 0..u128::BITS {
+                if mask & (1 << bit) == 0 {
+                    continue;
+                }
+                let pos = Pos2 {
+                    x: (bit % SIZE as u32) as i8,
+                    y: (bit / SIZE as u32) as i8,
+                };
+                if bit >= (SIZE * SIZE) as u32 || !is_in_bounds(pos) {
+                    return Err(BitboardError::OutOfBounds(bit));
+                }
+                if game[pos].is_some() {
+                    return Err(BitboardError::Overlap(pos));
+                }
+                game[pos] = Some(color);
+            }
+        }
+
+        Ok(game)
+    }
+}
@@ -0,0 +1,109 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::stackvec::StackVec;
+use crate::{is_in_bounds, Abalone, Color, Move, Pos2};
+
+/// The cells a move changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardDelta {
+    /// The changed cells, with their contents before and after the move.
+    pub changes: Vec<(Pos2, Option<Color>, Option<Color>)>,
+}
+
+/// The effects of a move, meant to be animated, see
+/// [`Abalone::submit_move_with_outcome`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveOutcome {
+    pub delta: BoardDelta,
+    /// Where each moved ball started and ended, see
+    /// [`Abalone::move_transitions`].
+    pub transitions: Vec<(Pos2, Pos2)>,
+    /// The color of the ball that was pushed off the board.
+    pub captured: Option<Color>,
+}
+
+impl Abalone {
+    /// The start and end positions of all balls that `mov` moves on the
+    /// current board. The end position of a ball pushed off the board is the
+    /// position just outside of it.
+    pub fn move_transitions(&self, mov: Move) -> Vec<(Pos2, Pos2)> {
+        let (first, last, dir) = match mov {
+            Move::PushedOff { first, last } | Move::PushedAway { first, last } => {
+                (first, last, (last - first).norm())
+            }
+            Move::Moved { dir, first, last } => (first, last, dir.vec()),
+            Move::Pass => return Vec::new(),
+        };
+
+        let vec = last - first;
+        let norm = vec.norm();
+        (0..=vec.mag())
+            .map(|i| {
+                let pos = first + norm * i;
+                (pos, pos + dir)
+            })
+            .collect()
+    }
+
+    /// The cells `mov` changes on the current board.
+    pub fn board_delta(&self, mov: Move) -> BoardDelta {
+        let changes = self.move_diff(mov).iter().copied().collect();
+        BoardDelta { changes }
+    }
+
+    /// The cells `mov` changes on the current board, with their contents
+    /// before and after the move. At most 5 balls move, so at most 6 cells
+    /// change.
+    pub fn move_diff(&self, mov: Move) -> StackVec<7, (Pos2, Option<Color>, Option<Color>)> {
+        let mut after = self.clone_position();
+        after.apply_move(mov);
+
+        let mut changes = StackVec::new();
+        for (from, to) in self.move_transitions(mov) {
+            for pos in [from, to] {
+                if !is_in_bounds(pos) || changes.iter().any(|&(p, _, _)| p == pos) {
+                    continue;
+                }
+                if self[pos] != after[pos] {
+                    changes.push((pos, self[pos], after[pos]));
+                }
+            }
+        }
+        changes
+    }
+
+    /// Submits the move and returns its effects on the board. Once the game
+    /// is finished the move isn't submitted, and the outcome is empty, see
+    /// [`Self::submit_move`].
+    pub fn submit_move_with_outcome(&mut self, mov: Move) -> MoveOutcome {
+        if self.outcome().is_some() {
+            return MoveOutcome::default();
+        }
+        let captured = match mov {
+            Move::PushedOff { last, .. } => self[last],
+            _ => None,
+        };
+        let outcome = MoveOutcome {
+            delta: self.board_delta(mov),
+            transitions: self.move_transitions(mov),
+            captured,
+        };
+        self.submit_move(mov);
+        outcome
+    }
+
+    /// Undoes the last move and returns it, along with the cells undoing it
+    /// changed. Returns [`None`] if there is no move to undo.
+    pub fn undo_move_with_delta(&mut self) -> Option<(Move, BoardDelta)> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        let mov = self.moves[self.move_idx - 1];
+        self.undo_move();
+        let changes = (self.board_delta(mov).changes.into_iter())
+            .map(|(pos, before, after)| (pos, after, before))
+            .collect();
+        Some((mov, BoardDelta { changes }))
+    }
+}
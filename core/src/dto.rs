@@ -1,6 +1,7 @@
 use serde_derive::{Deserialize, Serialize};
 
-use crate::{Abalone, Dir, Move, Pos2};
+use crate::delta::{BoardDelta, MoveOutcome};
+use crate::{Abalone, Color, Dir, Move, Outcome, Pos2, SIZE};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ClientMsg {
@@ -24,6 +25,12 @@ pub enum ClientMsg {
     RequestUndo,
     /// Allow the opponent to undo the last move.
     AllowUndo,
+    /// Pass without making a move, this is always rejected.
+    Pass,
+    /// Offer the opponent a draw.
+    OfferDraw,
+    /// Accept or decline the draw offered by the opponent.
+    AnswerDraw { accept: bool },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -42,10 +49,20 @@ pub enum ServerMsg {
     Sync(Room),
     /// Synchronize game state, but there isn't any.
     SyncEmpty,
-    /// A move was made.
-    AppliedMove(Move),
+    /// A move was made, with its effects on the board so clients can animate
+    /// it, and the outcome if the move finished the game.
+    AppliedMove(Move, MoveOutcome, Option<Outcome>),
     /// An undo was requested by the opponent.
     UndoRequested,
+    /// The last move was undone with the consent of both players, with its
+    /// reverted effects on the board and the color that is on turn again.
+    UndoneMove(Move, BoardDelta, Color),
+    /// A draw was offered by the opponent.
+    DrawOffered,
+    /// The draw offer was accepted, the game is drawn.
+    DrawAgreed,
+    /// The draw offer was declined, the game goes on.
+    DrawDeclined,
     /// An error occurred.
     Error(String),
 }
@@ -58,6 +75,33 @@ pub struct Room {
     pub players: [Option<User>; 2],
 }
 
+/// A read-only snapshot of a game for spectators, see
+/// [`Abalone::spectator_view`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpectatorView {
+    pub balls: [[Option<Color>; SIZE as usize]; SIZE as usize],
+    /// The number of opposing balls black and white have pushed off.
+    pub scores: (u8, u8),
+    pub turn: Color,
+    /// The played moves in [`crate::notation`], without moves that were
+    /// undone.
+    pub moves: Vec<String>,
+    pub winner: Option<Color>,
+}
+
+impl Abalone {
+    /// A snapshot of the current position and the moves played up to it.
+    pub fn spectator_view(&self) -> SpectatorView {
+        SpectatorView {
+            balls: self.balls,
+            scores: self.scores(),
+            turn: self.turn,
+            moves: self.recent_notation(self.move_idx),
+            winner: self.winner(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OpenRoom {
     pub id: RoomId,
@@ -0,0 +1,87 @@
+//! Instructive explanations of why a selection or move isn't possible, meant
+//! to be shown to players. Cells are written in [`crate::notation`].
+
+use crate::{Error, MoveError, Outcome, Pos2, SelectionError};
+
+impl Error {
+    pub fn explain(&self) -> String {
+        match self {
+            Error::Selection(e) => e.explain(),
+            Error::Move(e) => e.explain(),
+            Error::GameOver(Outcome::Win(winner)) => format!("The game is over, {winner} has won."),
+            Error::GameOver(Outcome::Draw(_)) => {
+                "The game is over, it ended in a draw.".to_string()
+            }
+            Error::InvalidNotation(s) => {
+                format!("{s} isn't a move, write moves like G5F4 or C3C5D3.")
+            }
+            Error::PassNotAllowed => {
+                "You can only pass if none of your balls can move.".to_string()
+            }
+        }
+    }
+}
+
+impl SelectionError {
+    pub fn explain(&self) -> String {
+        match self {
+            SelectionError::OutOfBounds(_) => "You can only select cells on the board.".to_string(),
+            SelectionError::WrongTurn(p) => format!(
+                "The ball at {} belongs to your opponent, select your own balls.",
+                p.to_notation()
+            ),
+            SelectionError::InvalidSet => {
+                "The selected balls have to form a straight line.".to_string()
+            }
+            SelectionError::MixedSet(mixed) => format!(
+                "The selection contains your opponent's balls at {}, select only your own.",
+                cells(mixed.iter())
+            ),
+            SelectionError::NotABall(empty) => format!(
+                "There isn't a ball at {}, select only cells with balls.",
+                cells(empty.iter())
+            ),
+            SelectionError::TooMany => "You can move at most 3 balls at once.".to_string(),
+            SelectionError::NoPossibleMove => {
+                "These balls can't move in any direction, try selecting others.".to_string()
+            }
+        }
+    }
+}
+
+impl MoveError {
+    pub fn explain(&self) -> String {
+        match self {
+            MoveError::PushedOff(pushed) => format!(
+                "This would push your own balls at {} off the board.",
+                cells(pushed.iter())
+            ),
+            MoveError::BlockedByOwn(p) => format!(
+                "Your own ball at {} is in the way, opposing balls can only be pushed into free cells or off the board.",
+                p.to_notation()
+            ),
+            MoveError::TooManyInferred { .. } => {
+                "You can't move more than 3 balls in a row, there are too many of yours in that direction.".to_string()
+            }
+            MoveError::TooManyOpposing { first, last } => {
+                let num = (*last - *first).mag() + 1;
+                let balls = if num == 1 { "ball" } else { "balls" };
+                format!(
+                    "You can't push {num} opposing {balls} with {num} or fewer of yours, you need more balls in a row than your opponent."
+                )
+            }
+            MoveError::NotFree(blocked) => {
+                let are = if blocked.len() == 1 { "is" } else { "are" };
+                format!(
+                    "Sideward moves need free cells, but {} {are} occupied.",
+                    cells(blocked.iter())
+                )
+            }
+        }
+    }
+}
+
+fn cells<'a>(cells: impl Iterator<Item = &'a Pos2>) -> String {
+    let cells: Vec<String> = cells.map(|p| p.to_notation()).collect();
+    cells.join(", ")
+}
@@ -0,0 +1,140 @@
+//! A one line position format, inspired by the FEN notation of chess.
+//!
+//! The rows of the board are listed from `y = 0` to `y = 8` separated by `/`,
+//! balls are written as `b` or `w` and runs of empty cells as their length.
+//! The color on turn follows after a space. The standard start position is:
+//! ```md
+//! bbbbb/bbbbbb/2bbb2/8/9/8/2www2/wwwwww/wwwww w
+//! ```
+
+use std::fmt;
+
+use crate::{Abalone, Color, NUM_STARTING_BALLS, SIZE};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// The side to move is missing.
+    MissingTurn,
+    /// The side to move isn't `b` or `w`.
+    InvalidTurn(String),
+    /// There aren't exactly 9 rows.
+    RowCount(usize),
+    /// The row doesn't contain the number of cells it has on the board.
+    RowLength { row: usize, len: usize },
+    /// The character is neither a ball nor a number of empty cells.
+    InvalidChar(char),
+    /// The color has more than [`NUM_STARTING_BALLS`] balls.
+    TooManyBalls(Color),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingTurn => write!(f, "Missing side to move"),
+            FenError::InvalidTurn(t) => write!(f, "Invalid side to move: {t}"),
+            FenError::RowCount(n) => write!(f, "Expected 9 rows, found {n}"),
+            FenError::RowLength { row, len } => {
+                let expected = row_cells(*row as i8).len();
+                write!(f, "Expected {expected} cells in row {row}, found {len}")
+            }
+            FenError::InvalidChar(c) => write!(f, "Invalid character: {c}"),
+            FenError::TooManyBalls(c) => {
+                write!(f, "More than {NUM_STARTING_BALLS} {c} balls")
+            }
+        }
+    }
+}
+
+impl Abalone {
+    /// Encodes the balls and the color on turn, see [`crate::fen`].
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for y in 0..SIZE {
+            if y > 0 {
+                fen.push('/');
+            }
+
+            let mut empty = 0;
+            for x in row_cells(y) {
+                let ball = match self[(x, y)] {
+                    Some(Color::Black) => 'b',
+                    Some(Color::White) => 'w',
+                    None => {
+                        empty += 1;
+                        continue;
+                    }
+                };
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(ball);
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.turn {
+            Color::Black => 'b',
+            Color::White => 'w',
+        });
+        fen
+    }
+
+    /// Decodes a position encoded by [`Self::to_fen`], the game starts with
+    /// an empty history.
+    pub fn from_fen(s: &str) -> Result<Abalone, FenError> {
+        let (board, turn) = s.trim().split_once(' ').ok_or(FenError::MissingTurn)?;
+
+        let mut game = Abalone::new();
+        game.turn = match turn.trim() {
+            "b" => Color::Black,
+            "w" => Color::White,
+            t => return Err(FenError::InvalidTurn(t.to_string())),
+        };
+
+        let rows: Vec<&str> = board.split('/').collect();
+        if rows.len() != SIZE as usize {
+            return Err(FenError::RowCount(rows.len()));
+        }
+
+        for (y, row) in rows.into_iter().enumerate() {
+            let mut cells = row_cells(y as i8);
+            let mut len = 0;
+            for c in row.chars() {
+                let (ball, num) = match c {
+                    'b' => (Some(Color::Black), 1),
+                    'w' => (Some(Color::White), 1),
+                    '1'..='9' => (None, c as usize - '0' as usize),
+                    _ => return Err(FenError::InvalidChar(c)),
+                };
+                len += num;
+                for _ in 0..num {
+                    let Some(x) = cells.next() else {
+                        return Err(FenError::RowLength { row: y, len });
+                    };
+                    game[(x, y as i8)] = ball;
+                }
+            }
+            if cells.next().is_some() {
+                return Err(FenError::RowLength { row: y, len });
+            }
+        }
+
+        for color in [Color::Black, Color::White] {
+            let count = game.iter().filter(|&(_, _, c)| c == Some(color)).count();
+            if count > NUM_STARTING_BALLS as usize {
+                return Err(FenError::TooManyBalls(color));
+            }
+        }
+
+        Ok(game)
+    }
+}
+
+/// The x coordinates of the cells in row `y`.
+fn row_cells(y: i8) -> std::ops::Range<i8> {
+    (y - 4).max(0)..(y + 5).min(SIZE)
+}
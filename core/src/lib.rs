@@ -1,15 +1,28 @@
+use std::collections::{HashSet, VecDeque};
 use std::{fmt, ops};
 
 use serde_derive::{Deserialize, Serialize};
 
 use crate::stackvec::StackVec;
 
+pub mod ai;
+pub mod bitboard;
+pub mod delta;
 pub mod dto;
+pub mod explain;
+pub mod fen;
+pub mod notation;
+pub mod report;
+pub mod script;
 pub mod stackvec;
+pub mod tablebase;
 #[cfg(test)]
 mod test;
+pub mod transcript;
 
 pub const NUM_STARTING_BALLS: u8 = 14;
+/// The number of opposing balls that have to be pushed off to win the game.
+pub const NUM_BALLS_TO_WIN: u8 = 6;
 
 const UNIT_X: Vec2 = Vec2 { x: 1, y: 0 };
 const UNIT_Y: Vec2 = Vec2 { x: 0, y: 1 };
@@ -17,6 +30,30 @@ const UNIT_Z: Vec2 = Vec2 { x: 1, y: 1 };
 
 const SIZE: i8 = 9;
 
+/// The seed the keys of [`Abalone::zobrist_hash`] are generated from, so
+/// hashes are the same across runs, processes and peers using it.
+pub const ZOBRIST_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+const ZOBRIST_KEYS: ZobristKeys = ZobristKeys::generate(ZOBRIST_SEED);
+
+const DIRS: [Dir; 6] = [
+    Dir::PosX,
+    Dir::PosY,
+    Dir::PosZ,
+    Dir::NegX,
+    Dir::NegY,
+    Dir::NegZ,
+];
+
+/// The directions in sextant order, see [`Dir::from_sextant`].
+const SEXTANTS: [Dir; 6] = [
+    Dir::PosX,
+    Dir::PosZ,
+    Dir::PosY,
+    Dir::NegX,
+    Dir::NegZ,
+    Dir::NegY,
+];
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Move {
     /// Pushed opposing color, off the board.
@@ -41,12 +78,54 @@ pub enum Move {
         /// Last ball, of the same color, that was pushed.
         last: Pos2,
     },
+    /// The turn was passed without moving any balls, see
+    /// [`Abalone::pass_turn`].
+    Pass,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl Move {
+    /// The direction in which the balls were moved.
+    ///
+    /// Returns [`None`] if the move doesn't span a straight line, or is a
+    /// [`Move::Pass`].
+    pub fn dir(&self) -> Option<Dir> {
+        match *self {
+            Move::PushedOff { first, last } | Move::PushedAway { first, last } => {
+                let vec = last - first;
+                if !vec.is_multiple_of_unit_vec() {
+                    return None;
+                }
+                vec.norm().unit_vec()
+            }
+            Move::Moved { dir, .. } => Some(dir),
+            Move::Pass => None,
+        }
+    }
+
+    /// The positions of the opposing balls the move pushes off the board, on
+    /// the board before the move. Empty for moves that don't push off a ball.
+    ///
+    /// NOTE: a move can push off at most one ball, since the pushing balls
+    /// have to outnumber the opposing ones.
+    pub fn pushed_off_marbles(&self) -> StackVec<3, Pos2> {
+        let mut pushed_off = StackVec::new();
+        if let Move::PushedOff { last, .. } = *self {
+            pushed_off.push(last);
+        }
+        pushed_off
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Error {
     Selection(SelectionError),
     Move(MoveError),
+    /// The game is already finished, see [`Abalone::check_game_move`].
+    GameOver(Outcome),
+    /// The text isn't a move in [`notation`], see [`Abalone::parse_move`].
+    InvalidNotation(String),
+    /// The player has a legal move and can't pass, see [`Abalone::check_pass`].
+    PassNotAllowed,
 }
 
 impl std::fmt::Display for Error {
@@ -54,6 +133,20 @@ impl std::fmt::Display for Error {
         match self {
             Error::Selection(e) => write!(f, "Selection error: {e}"),
             Error::Move(e) => write!(f, "Move error: {e}"),
+            Error::GameOver(Outcome::Win(winner)) => write!(f, "Game over: {winner} won"),
+            Error::GameOver(Outcome::Draw(_)) => write!(f, "Game over: draw"),
+            Error::InvalidNotation(s) => write!(f, "Invalid notation: {s}"),
+            Error::PassNotAllowed => write!(f, "Passing isn't allowed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Selection(e) => Some(e),
+            Error::Move(e) => Some(e),
+            Error::GameOver(_) | Error::InvalidNotation(_) | Error::PassNotAllowed => None,
         }
     }
 }
@@ -70,8 +163,10 @@ impl From<MoveError> for Error {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SelectionError {
+    /// The position isn't a cell of the board.
+    OutOfBounds(Pos2),
     /// It's the other color's turn.
     WrongTurn(Pos2),
     /// The first and last balls span an invalid set of balls, e.g. the vector
@@ -91,6 +186,7 @@ pub enum SelectionError {
 impl std::fmt::Display for SelectionError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            SelectionError::OutOfBounds(p) => write!(f, "Out of bounds at {p}"),
             SelectionError::WrongTurn(p) => write!(f, "Wrong turn at {p}"),
             SelectionError::InvalidSet => write!(f, "Invalid set"),
             SelectionError::MixedSet(mixed_set) => {
@@ -113,7 +209,9 @@ impl std::fmt::Display for SelectionError {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl std::error::Error for SelectionError {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MoveError {
     /// Would push off your own ball.
     PushedOff(StackVec<3, Pos2>),
@@ -166,7 +264,126 @@ impl std::fmt::Display for MoveError {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+impl std::error::Error for MoveError {}
+
+/// Why a selection can't be extended to a position, see [`extend_selection`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtendError {
+    /// The position isn't in line with the selection.
+    NotCollinear,
+    /// The position is in line with the selection, but not next to it.
+    NotAdjacent,
+    /// The selection already spans 3 balls.
+    WouldExceedThree,
+}
+
+impl std::fmt::Display for ExtendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtendError::NotCollinear => write!(f, "Not in line with the selection"),
+            ExtendError::NotAdjacent => write!(f, "Not next to the selection"),
+            ExtendError::WouldExceedThree => write!(f, "More than 3 balls"),
+        }
+    }
+}
+
+/// An inconsistent game state, see [`Abalone::validate_invariants`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantError {
+    /// A ball lies outside of the board.
+    OutOfBounds(Pos2),
+    /// The color has more than [`NUM_STARTING_BALLS`] balls.
+    TooManyBalls(Color),
+    /// The current move lies past the end of the history.
+    MoveIdx { move_idx: usize, num_moves: usize },
+}
+
+impl std::fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::OutOfBounds(p) => write!(f, "Ball out of bounds at {p}"),
+            InvariantError::TooManyBalls(c) => write!(f, "Too many {c} balls"),
+            InvariantError::MoveIdx {
+                move_idx,
+                num_moves,
+            } => write!(f, "Move index {move_idx} past {num_moves} moves"),
+        }
+    }
+}
+
+/// The balls can't be set up, see [`Abalone::from_balls`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupError {
+    /// A ball lies outside of the board.
+    OutOfBounds(Pos2),
+    /// The color has more than [`NUM_STARTING_BALLS`] balls.
+    TooManyBalls(Color),
+}
+
+impl std::fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::OutOfBounds(p) => write!(f, "Ball out of bounds at {p}"),
+            SetupError::TooManyBalls(c) => write!(f, "Too many {c} balls"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApplyError {
+    /// The move isn't legal on the current board.
+    Illegal(Error),
+    /// The move is legal, but would have a different effect on the current board.
+    Mismatch {
+        /// The move that was supposed to be applied.
+        expected: Move,
+        /// The move that would actually be applied.
+        actual: Move,
+    },
+    /// The position changed since the move was proposed.
+    PositionChanged {
+        /// The hash of the position the move was proposed for.
+        expected: u64,
+        /// The hash of the current position.
+        actual: u64,
+    },
+}
+
+impl std::fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::Illegal(e) => write!(f, "Illegal move: {e}"),
+            ApplyError::Mismatch { expected, actual } => {
+                write!(f, "Move mismatch: expected {expected:?} found {actual:?}")
+            }
+            ApplyError::PositionChanged { expected, actual } => {
+                write!(
+                    f,
+                    "Position changed: expected {expected:016x} found {actual:016x}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApplyError::Illegal(e) => Some(e),
+            ApplyError::Mismatch { .. } | ApplyError::PositionChanged { .. } => None,
+        }
+    }
+}
+
+impl From<Error> for ApplyError {
+    fn from(value: Error) -> Self {
+        Self::Illegal(value)
+    }
+}
+
+/// The discriminants are part of the wire protocol and stay stable, `0` for
+/// black and `1` for white, see the [`TryFrom<u8>`] impl for the reverse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Color {
     Black = 0,
     White = 1,
@@ -194,7 +411,8 @@ impl TryFrom<u8> for Color {
 }
 
 impl Color {
-    fn opposite(&self) -> Self {
+    /// The color of the opponent.
+    pub fn opposite(&self) -> Self {
         match self {
             Self::Black => Self::White,
             Self::White => Self::Black,
@@ -219,12 +437,25 @@ impl Color {
 /// 8 / . . . . * * * * *
 ///  y
 /// ```
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Pos2 {
     pub x: i8,
     pub y: i8,
 }
 
+/// Positions are ordered row-major, like [`Abalone::iter`].
+impl Ord for Pos2 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
+}
+
+impl PartialOrd for Pos2 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::fmt::Display for Pos2 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self { x, y } = self;
@@ -273,9 +504,29 @@ impl From<(i8, i8)> for Pos2 {
 
 impl Pos2 {
     pub const ZERO: Self = Self { x: 0, y: 0 };
+
+    /// Rotates the cell by `steps` times 60 degrees around the center cell
+    /// `(4, 4)`, in the direction of [`Vec2::rotate_60`]. Cells on the board
+    /// stay on the board, and 6 steps are a full turn.
+    pub fn rotate(&self, steps: u8) -> Pos2 {
+        let center = Pos2 { x: 4, y: 4 };
+        let mut vec = *self - center;
+        for _ in 0..steps % 6 {
+            vec = vec.rotate_60();
+        }
+        center + vec
+    }
+
+    /// The adjacent cells on the board, fewer than 6 on the edge.
+    pub fn neighbors(&self) -> impl Iterator<Item = Pos2> {
+        let pos = *self;
+        DIRS.into_iter()
+            .map(move |d| pos + d.vec())
+            .filter(|&p| is_in_bounds(p))
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: i8,
     pub y: i8,
@@ -350,7 +601,30 @@ impl Vec2 {
             return *self == other;
         }
 
-        self.x * other.y == self.y * other.x
+        self.cross(other) == 0
+    }
+
+    /// The dot product of the coordinates.
+    ///
+    /// NOTE: the axes aren't orthogonal on the board, so this doesn't reflect
+    /// the angle between the vectors as drawn.
+    pub fn dot(self, other: Vec2) -> i32 {
+        self.x as i32 * other.x as i32 + self.y as i32 * other.y as i32
+    }
+
+    /// The z component of the cross product of the coordinates, `0` if the
+    /// vectors are parallel.
+    pub fn cross(self, other: Vec2) -> i32 {
+        self.x as i32 * other.y as i32 - self.y as i32 * other.x as i32
+    }
+
+    /// Rotates the vector counterclockwise by 60 degrees around the origin,
+    /// mapping X onto Z, Z onto Y and Y onto -X.
+    pub fn rotate_60(self) -> Vec2 {
+        Vec2 {
+            x: self.x - self.y,
+            y: self.x,
+        }
     }
 
     pub fn unit_vec(&self) -> Option<Dir> {
@@ -367,14 +641,24 @@ impl Vec2 {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The discriminants are part of the wire protocol and stay stable, see
+/// [`Dir::to_u8`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Dir {
-    PosX,
-    PosY,
-    PosZ,
-    NegX,
-    NegY,
-    NegZ,
+    PosX = 0,
+    PosY = 1,
+    PosZ = 2,
+    NegX = 3,
+    NegY = 4,
+    NegZ = 5,
+}
+
+impl ops::Neg for Dir {
+    type Output = Dir;
+
+    fn neg(self) -> Self::Output {
+        self.opposite()
+    }
 }
 
 impl Dir {
@@ -388,14 +672,204 @@ impl Dir {
             Self::NegZ => -UNIT_Z,
         }
     }
+
+    /// All 6 directions, in declaration order.
+    pub fn all() -> [Dir; 6] {
+        DIRS
+    }
+
+    /// The next direction in sextant order, see [`Self::from_sextant`]. On
+    /// screen, where the y axis points down, this rotates clockwise by 60
+    /// degrees.
+    pub fn rotate_cw(&self) -> Dir {
+        Dir::from_sextant(self.sextant() + 1)
+    }
+
+    /// The previous direction in sextant order, the inverse of
+    /// [`Self::rotate_cw`].
+    pub fn rotate_ccw(&self) -> Dir {
+        Dir::from_sextant(self.sextant() + 5)
+    }
+
+    /// The direction pointing the other way.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::PosX => Self::NegX,
+            Self::PosY => Self::NegY,
+            Self::PosZ => Self::NegZ,
+            Self::NegX => Self::PosX,
+            Self::NegY => Self::PosY,
+            Self::NegZ => Self::PosZ,
+        }
+    }
+
+    /// The direction of the sextant `idx`, wrapping around after 6.
+    ///
+    /// Sextants are numbered in the order [`Vec2::rotate_60`] cycles through
+    /// the directions, starting at [`Dir::PosX`]. On screen, where the y axis
+    /// points down, this is clockwise.
+    pub fn from_sextant(idx: u8) -> Dir {
+        SEXTANTS[(idx % 6) as usize]
+    }
+
+    /// The compact encoding of the direction, from `0` for [`Dir::PosX`] to
+    /// `5` for [`Dir::NegZ`] in declaration order.
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Decodes a direction encoded by [`Self::to_u8`], returns [`None`] for
+    /// values above `5`.
+    pub fn from_u8(value: u8) -> Option<Dir> {
+        match value {
+            0 => Some(Self::PosX),
+            1 => Some(Self::PosY),
+            2 => Some(Self::PosZ),
+            3 => Some(Self::NegX),
+            4 => Some(Self::NegY),
+            5 => Some(Self::NegZ),
+            _ => None,
+        }
+    }
+
+    /// The sextant index of the direction, see [`Self::from_sextant`].
+    pub fn sextant(self) -> u8 {
+        match self {
+            Self::PosX => 0,
+            Self::PosZ => 1,
+            Self::PosY => 2,
+            Self::NegX => 3,
+            Self::NegZ => 4,
+            Self::NegY => 5,
+        }
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Abalone {
     pub balls: [[Option<Color>; SIZE as usize]; SIZE as usize],
     pub moves: Vec<Move>,
     pub move_idx: usize,
+    /// The number of played moves that were dropped from the start of the
+    /// history, see [`Rules::max_history`].
+    #[serde(default)]
+    pub dropped_moves: usize,
     pub turn: Color,
+    #[serde(default)]
+    pub rules: Rules,
+    /// Both players agreed to a draw, see [`Self::agree_draw`].
+    #[serde(default)]
+    pub draw_agreed: bool,
+    #[serde(default)]
+    events: Vec<GameEvent>,
+}
+
+/// Compares everything but the [`Abalone::event_log`], so undoing and redoing
+/// a move results in an equal game.
+impl PartialEq for Abalone {
+    fn eq(&self, other: &Self) -> bool {
+        let Abalone {
+            balls,
+            moves,
+            move_idx,
+            dropped_moves,
+            turn,
+            rules,
+            draw_agreed,
+            events: _,
+        } = self;
+        *balls == other.balls
+            && *moves == other.moves
+            && *move_idx == other.move_idx
+            && *dropped_moves == other.dropped_moves
+            && *turn == other.turn
+            && *rules == other.rules
+            && *draw_agreed == other.draw_agreed
+    }
+}
+
+/// What a run of opposing balls ends at, see [`Abalone::opposing_run`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunEnd {
+    /// A free field.
+    Empty,
+    /// A ball of the pushing color.
+    Own,
+    /// The edge of the board.
+    Edge,
+}
+
+/// The result of a finished game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Win(Color),
+    Draw(DrawReason),
+}
+
+/// Whether the game goes on and who has to move, see [`Abalone::status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Ongoing { turn: Color },
+    Win { winner: Color },
+    Draw,
+}
+
+impl GameStatus {
+    /// Whether the game is finished and no more moves should be prompted.
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            GameStatus::Ongoing { .. } => false,
+            GameStatus::Win { .. } | GameStatus::Draw => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrawReason {
+    /// The [`Rules::move_limit`] was reached.
+    MoveLimit,
+    /// Both players agreed to a draw, see [`Abalone::agree_draw`].
+    Agreement,
+}
+
+/// A change of the game, see [`Abalone::event_log`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    Move(Move),
+    Undo,
+    Redo,
+    /// The game was reset to the start of its history, see
+    /// [`Abalone::reset`].
+    Reset,
+    /// The game finished.
+    Outcome(Outcome),
+}
+
+/// Optional rules, that deviate from a standard game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// The game is drawn if there is no winner after this many moves.
+    pub move_limit: Option<usize>,
+    /// Only this many of the most recent moves are kept in the history, older
+    /// ones are dropped and can't be undone anymore.
+    pub max_history: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Standing {
+    /// The color has pushed off enough opposing balls.
+    Win(Color),
+    /// The game is still in progress.
+    Ongoing,
+    /// The move limit was reached without a winner.
+    DrawByLimit {
+        /// The number of white balls black pushed off.
+        black: u8,
+        /// The number of black balls white pushed off.
+        white: u8,
+    },
+    /// Both players agreed to a draw.
+    DrawByAgreement,
 }
 
 impl fmt::Display for Abalone {
@@ -440,9 +914,9 @@ impl Default for Abalone {
     }
 }
 
-impl Abalone {
-    /// Returns a new game with the default start position as shown below:
-    ///
+/// The layout of the balls at the start of a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartPosition {
     /// ```md
     ///               0 1 2 3 4 5 6 7 8
     ///            # - - - - - - - - - x
@@ -457,37 +931,168 @@ impl Abalone {
     /// 8 / . . . . w w w w w
     ///  y
     /// ```
+    #[default]
+    Standard,
+    /// ```md
+    ///               0 1 2 3 4 5 6 7 8
+    ///            # - - - - - - - - - x
+    ///         0 / b b * w w . . . .
+    ///        1 / b b b w w w . . .
+    ///       2 / * b b * w w * . .
+    ///      3 / * * * * * * * * .
+    ///     4 / * * * * * * * * *
+    ///    5 / . * * * * * * * *
+    ///   6 / . . * w w * b b *
+    ///  7 / . . . w w w b b b
+    /// 8 / . . . . w w * b b
+    ///  y
+    /// ```
+    BelgianDaisy,
+    /// ```md
+    ///               0 1 2 3 4 5 6 7 8
+    ///            # - - - - - - - - - x
+    ///         0 / * * * * * . . . .
+    ///        1 / b b * * w w . . .
+    ///       2 / b b b * w w w . .
+    ///      3 / * b b * * w w * .
+    ///     4 / * * * * * * * * *
+    ///    5 / . * w w * * b b *
+    ///   6 / . . w w w * b b b
+    ///  7 / . . . w w * * b b
+    /// 8 / . . . . * * * * *
+    ///  y
+    /// ```
+    GermanDaisy,
+    /// ```md
+    ///               0 1 2 3 4 5 6 7 8
+    ///            # - - - - - - - - - x
+    ///         0 / b b b b b . . . .
+    ///        1 / * * * * * b . . .
+    ///       2 / * * * * * * b . .
+    ///      3 / * b b b b b b * .
+    ///     4 / * b * * * * * w *
+    ///    5 / . * w w w w w w *
+    ///   6 / . . w * * * * * *
+    ///  7 / . . . w * * * * *
+    /// 8 / . . . . w w w w w
+    ///  y
+    /// ```
+    Snakes,
+}
+
+impl StartPosition {
+    pub const ALL: [Self; 4] = [
+        Self::Standard,
+        Self::BelgianDaisy,
+        Self::GermanDaisy,
+        Self::Snakes,
+    ];
+}
+
+impl Abalone {
+    /// Returns a new game with the [`StartPosition::Standard`] layout.
     pub fn new() -> Self {
+        Self::with_start_position(StartPosition::Standard)
+    }
+
+    /// Returns a new game with the balls laid out according to `start`.
+    pub fn with_start_position(start: StartPosition) -> Self {
         let mut game = Self {
             balls: [[None; SIZE as usize]; SIZE as usize],
             moves: Vec::new(),
             move_idx: 0,
+            dropped_moves: 0,
             turn: Color::White,
+            rules: Rules::default(),
+            draw_agreed: false,
+            events: Vec::new(),
         };
 
-        for i in 0..5 {
-            game[(i, 0)] = Some(Color::Black);
-        }
-        for i in 0..6 {
-            game[(i, 1)] = Some(Color::Black);
-        }
-        for i in 2..5 {
-            game[(i, 2)] = Some(Color::Black);
-        }
+        match start {
+            StartPosition::Standard => {
+                for i in 0..5 {
+                    game[(i, 0)] = Some(Color::Black);
+                }
+                for i in 0..6 {
+                    game[(i, 1)] = Some(Color::Black);
+                }
+                for i in 2..5 {
+                    game[(i, 2)] = Some(Color::Black);
+                }
 
-        for i in 4..9 {
-            game[(i, 8)] = Some(Color::White);
-        }
-        for i in 3..9 {
-            game[(i, 7)] = Some(Color::White);
-        }
-        for i in 4..7 {
-            game[(i, 6)] = Some(Color::White);
+                for i in 4..9 {
+                    game[(i, 8)] = Some(Color::White);
+                }
+                for i in 3..9 {
+                    game[(i, 7)] = Some(Color::White);
+                }
+                for i in 4..7 {
+                    game[(i, 6)] = Some(Color::White);
+                }
+            }
+            StartPosition::BelgianDaisy => {
+                game.place_daisy((1, 1), Color::Black);
+                game.place_daisy((7, 7), Color::Black);
+                game.place_daisy((4, 1), Color::White);
+                game.place_daisy((4, 7), Color::White);
+            }
+            StartPosition::GermanDaisy => {
+                game.place_daisy((1, 2), Color::Black);
+                game.place_daisy((7, 6), Color::Black);
+                game.place_daisy((5, 2), Color::White);
+                game.place_daisy((3, 6), Color::White);
+            }
+            StartPosition::Snakes => {
+                let mut snake = vec![(5, 1), (6, 2), (1, 4)];
+                snake.extend((0..5).map(|x| (x, 0)));
+                snake.extend((1..7).map(|x| (x, 3)));
+                for (x, y) in snake {
+                    game[(x, y)] = Some(Color::Black);
+                    game[(SIZE - 1 - x, SIZE - 1 - y)] = Some(Color::White);
+                }
+            }
         }
 
         game
     }
 
+    /// Sets the color on turn, meant for setting up games, e.g.
+    /// `Abalone::new().with_turn(Color::Black)`. The history is kept as is.
+    pub fn with_turn(mut self, turn: Color) -> Self {
+        self.turn = turn;
+        self
+    }
+
+    /// Returns a new game with the `balls` and `turn`, after checking that all
+    /// balls are on the board and neither color has too many. The history is
+    /// empty.
+    pub fn from_balls(
+        balls: [[Option<Color>; SIZE as usize]; SIZE as usize],
+        turn: Color,
+    ) -> Result<Self, SetupError> {
+        let game = Self {
+            balls,
+            turn,
+            ..Self::new()
+        };
+        match game.validate_invariants() {
+            Ok(()) => Ok(game),
+            Err(InvariantError::OutOfBounds(p)) => Err(SetupError::OutOfBounds(p)),
+            Err(InvariantError::TooManyBalls(c)) => Err(SetupError::TooManyBalls(c)),
+            Err(InvariantError::MoveIdx { .. }) => unreachable!("the history is empty"),
+        }
+    }
+
+    /// Places a ball at the center and all 6 surrounding cells.
+    fn place_daisy(&mut self, center: impl Into<Pos2>, color: Color) {
+        let center = center.into();
+        self[center] = Some(color);
+        for v in [UNIT_X, UNIT_Y, UNIT_Z] {
+            self[center + v] = Some(color);
+            self[center - v] = Some(color);
+        }
+    }
+
     pub fn get(&self, pos: impl Into<Pos2>) -> Option<&Option<Color>> {
         let pos = pos.into();
         if !is_in_bounds(pos) {
@@ -507,34 +1112,85 @@ impl Abalone {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (i8, i8, Option<Color>)> + '_ {
-        (0..SIZE * SIZE).filter_map(move |i| {
-            let y = i / SIZE;
-            let x = i % SIZE;
-            let val = *self.get((x, y))?;
-            Some((x, y, val))
+        board_cells().map(|p| (p.x, p.y, self[p]))
+    }
+
+    /// Mutably iterates over all positions on the board, in row-major order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos2, &mut Option<Color>)> + '_ {
+        self.balls.iter_mut().enumerate().flat_map(|(y, row)| {
+            row.iter_mut().enumerate().filter_map(move |(x, val)| {
+                let pos = Pos2 {
+                    x: x as i8,
+                    y: y as i8,
+                };
+                is_in_bounds(pos).then_some((pos, val))
+            })
         })
     }
 
+    /// All positions on the board ordered by their distance to the center,
+    /// positions with the same distance in row-major order.
+    pub fn cells_center_out(&self) -> impl Iterator<Item = Pos2> {
+        let center = Pos2 { x: 4, y: 4 };
+        let mut cells: Vec<Pos2> = board_cells().collect();
+        cells.sort_by_key(|&p| (p - center).mag());
+        cells.into_iter()
+    }
+
+    /// The cells starting at `from` and stepping in `dir` until the edge of
+    /// the board, along with their contents. Yields nothing if `from` isn't
+    /// on the board.
+    pub fn line(&self, from: Pos2, dir: Dir) -> impl Iterator<Item = (Pos2, Option<Color>)> + '_ {
+        let vec = dir.vec();
+        std::iter::successors(Some(from), move |&p| Some(p + vec))
+            .map_while(|p| self.get(p).map(|&c| (p, c)))
+    }
+
+    /// Checks only the shape of the selection spanned by `first` and `last`,
+    /// regardless of the balls on the board: both ends have to be on the
+    /// board and span at most 3 cells along one axis.
+    pub fn valid_selection_geometry(first: Pos2, last: Pos2) -> Result<(), SelectionError> {
+        for p in [first, last] {
+            if !is_in_bounds(p) {
+                return Err(SelectionError::OutOfBounds(p));
+            }
+        }
+
+        let vec = last - first;
+        if !vec.is_multiple_of_unit_vec() {
+            return Err(SelectionError::InvalidSet);
+        }
+        if vec.mag() >= 3 {
+            return Err(SelectionError::TooMany);
+        }
+        Ok(())
+    }
+
     pub fn check_selection(&self, selection: [Pos2; 2]) -> Result<(), SelectionError> {
-        let dirs = [
-            Dir::PosX,
-            Dir::PosY,
-            Dir::PosZ,
-            Dir::NegX,
-            Dir::NegY,
-            Dir::NegZ,
-        ];
-        for dir in dirs {
+        for dir in Dir::all() {
             match self.check_move(selection, dir) {
                 Ok(_) => return Ok(()),
                 Err(Error::Selection(e)) => return Err(e),
-                Err(Error::Move(_)) => continue,
+                Err(
+                    Error::Move(_)
+                    | Error::GameOver(_)
+                    | Error::InvalidNotation(_)
+                    | Error::PassNotAllowed,
+                ) => continue,
             }
         }
         Err(SelectionError::NoPossibleMove)
     }
 
+    /// Checks the move against the balls on the board, regardless of whether
+    /// the game is already over, see [`Self::check_game_move`].
     pub fn check_move(&self, [mut first, mut last]: [Pos2; 2], dir: Dir) -> Result<Move, Error> {
+        for p in [first, last] {
+            if !is_in_bounds(p) {
+                return Err(SelectionError::OutOfBounds(p).into());
+            }
+        }
+
         if let Some(&Some(color)) = self.get(first) {
             if color != self.turn {
                 return Err(SelectionError::WrongTurn(first).into());
@@ -622,34 +1278,22 @@ impl Abalone {
                 .into());
             }
 
-            let opposing_color = color.opposite();
-            let mut opposing_force = 1;
-
-            loop {
-                let p = opposing_first + dir.vec() * opposing_force;
-                match self.get(p) {
-                    Some(&Some(c)) => {
-                        if c != opposing_color {
-                            return Err(MoveError::BlockedByOwn(p).into());
-                        }
-                        if opposing_force >= force - 1 {
-                            return Err(MoveError::TooManyOpposing {
-                                first: opposing_first,
-                                last: p,
-                            }
-                            .into());
-                        }
-                        opposing_force += 1;
-                    }
-                    Some(None) => {
-                        let last = opposing_first + dir.vec() * (opposing_force - 1);
-                        return Ok(Move::PushedAway { first, last });
-                    }
-                    None => {
-                        let last = opposing_first + dir.vec() * (opposing_force - 1);
-                        return Ok(Move::PushedOff { first, last });
-                    }
+            let (opposing_force, end) = self.opposing_run(opposing_first, dir, color);
+            let opposing_force = opposing_force as i8;
+            if opposing_force >= force {
+                let last = opposing_first + dir.vec() * (force - 1);
+                return Err(MoveError::TooManyOpposing {
+                    first: opposing_first,
+                    last,
                 }
+                .into());
+            }
+
+            let last = opposing_first + dir.vec() * (opposing_force - 1);
+            match end {
+                RunEnd::Empty => Ok(Move::PushedAway { first, last }),
+                RunEnd::Own => Err(MoveError::BlockedByOwn(last + dir.vec()).into()),
+                RunEnd::Edge => Ok(Move::PushedOff { first, last }),
             }
         } else {
             // sideward motion
@@ -699,13 +1343,791 @@ impl Abalone {
         }
     }
 
+    /// Checks moving the ball at `pos`, the same as [`Self::check_move`] with
+    /// `[pos, pos]` as selection.
+    pub fn check_single(&self, pos: Pos2, dir: Dir) -> Result<Move, Error> {
+        self.check_move([pos, pos], dir)
+    }
+
+    /// Whether the ball at `pos` can move, on its own or as part of a line, if
+    /// its color were on turn.
+    pub fn can_ball_move(&self, pos: Pos2) -> bool {
+        let Some(&Some(color)) = self.get(pos) else {
+            return false;
+        };
+        let mut game = self.clone_position();
+        game.turn = color;
+
+        // every selection of up to 3 balls of `color` along an axis, that
+        // contains `pos`
+        let own = |p: Pos2| game.get(p) == Some(&Some(color));
+        let mut selections = [UNIT_X, UNIT_Y, UNIT_Z].into_iter().flat_map(|axis| {
+            (-2..=0).flat_map(move |start| {
+                (0..=start + 2)
+                    .filter(move |&end| (start..=end).all(|i| own(pos + axis * i)))
+                    .map(move |end| [pos + axis * start, pos + axis * end])
+            })
+        });
+        selections.any(|selection| {
+            DIRS.into_iter()
+                .any(|dir| game.check_move(selection, dir).is_ok())
+        })
+    }
+
+    /// The balls of `color` that can't move in any direction, in row-major
+    /// order, see [`Self::can_ball_move`].
+    pub fn immobile(&self, color: Color) -> Vec<Pos2> {
+        (self.iter())
+            .filter(|&(_, _, c)| c == Some(color))
+            .map(|(x, y, _)| Pos2 { x, y })
+            .filter(|&pos| !self.can_ball_move(pos))
+            .collect()
+    }
+
+    /// A bit mask of the balls of `color` that can move, see
+    /// [`Self::can_ball_move`]. Bit `y * 9 + x` corresponds to the cell at
+    /// `(x, y)`, like in the [`bitboard`] masks.
+    pub fn movable_balls(&self, color: Color) -> u128 {
+        let mut mask = 0;
+        for (x, y, c) in self.iter() {
+            let pos = Pos2 { x, y };
+            if c == Some(color) && self.can_ball_move(pos) {
+                mask |= 1 << (y as u32 * SIZE as u32 + x as u32);
+            }
+        }
+        mask
+    }
+
+    /// Checks that `mov` is legal on the current board, and has exactly the
+    /// same effect as the move [`Self::check_move`] would return. Once the
+    /// game is finished, every move is rejected like by
+    /// [`Self::check_game_move`].
+    ///
+    /// A [`Move::Pass`] is only accepted if the player has no legal move, see
+    /// [`Self::check_pass`].
+    pub fn validate_move(&self, mov: Move) -> Result<(), ApplyError> {
+        if mov == Move::Pass {
+            self.check_pass()?;
+            return Ok(());
+        }
+        if let Some(outcome) = self.outcome() {
+            return Err(Error::GameOver(outcome).into());
+        }
+        let Some(dir) = mov.dir() else {
+            return Err(Error::from(SelectionError::InvalidSet).into());
+        };
+        let selection = match mov {
+            // the remaining balls are inferred
+            Move::PushedOff { first, .. } | Move::PushedAway { first, .. } => [first, first],
+            Move::Moved { first, last, .. } => [first, last],
+            Move::Pass => unreachable!(),
+        };
+
+        let actual = self.check_move(selection, dir)?;
+        if actual != mov {
+            return Err(ApplyError::Mismatch {
+                expected: mov,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The number of opposing balls black and white have pushed off the board,
+    /// see [`Self::captured_from_board`].
+    pub fn scores(&self) -> (u8, u8) {
+        self.captured_from_board()
+    }
+
+    /// The number of opposing balls black and white have pushed off the board,
+    /// derived from the balls missing of each color. Since no separate
+    /// counters are kept, this also holds for games loaded without history,
+    /// e.g. by [`Self::from_fen`] or [`crate::bitboard`].
+    pub fn captured_from_board(&self) -> (u8, u8) {
+        let mut black_score = NUM_STARTING_BALLS;
+        let mut white_score = NUM_STARTING_BALLS;
+        for (_, _, c) in self.iter() {
+            match c {
+                Some(Color::Black) => white_score = white_score.saturating_sub(1),
+                Some(Color::White) => black_score = black_score.saturating_sub(1),
+                None => (),
+            }
+        }
+        (black_score, white_score)
+    }
+
+    /// Checks that the state is consistent, meant for debugging and tests.
+    pub fn validate_invariants(&self) -> Result<(), InvariantError> {
+        let mut counts = [0; 2];
+        for (y, row) in self.balls.iter().enumerate() {
+            for (x, ball) in row.iter().enumerate() {
+                let Some(color) = *ball else {
+                    continue;
+                };
+                let pos = Pos2 {
+                    x: x as i8,
+                    y: y as i8,
+                };
+                if !is_in_bounds(pos) {
+                    return Err(InvariantError::OutOfBounds(pos));
+                }
+                counts[color as usize] += 1;
+            }
+        }
+
+        for color in [Color::Black, Color::White] {
+            if counts[color as usize] > NUM_STARTING_BALLS {
+                return Err(InvariantError::TooManyBalls(color));
+            }
+        }
+        if self.move_idx > self.moves.len() {
+            return Err(InvariantError::MoveIdx {
+                move_idx: self.move_idx,
+                num_moves: self.moves.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The number of balls `perspective` has pushed off minus the number of
+    /// balls the opponent has pushed off.
+    pub fn material_balance(&self, perspective: Color) -> i8 {
+        let (black_score, white_score) = self.scores();
+        let balance = black_score as i8 - white_score as i8;
+        match perspective {
+            Color::Black => balance,
+            Color::White => -balance,
+        }
+    }
+
+    /// Every legal move of the current player with its score, best first.
+    ///
+    /// The score is the [`ai::score`] of the current player after searching
+    /// `depth` plies ahead, at least the move itself. Moves with the same
+    /// score keep the order of [`Self::legal_moves_iter`]. The work grows
+    /// exponentially with the depth, so it should stay small.
+    pub fn rank_moves(&self, depth: u32) -> Vec<(Move, i32)> {
+        let mut ranked: Vec<(Move, i32)> = (self.legal_moves_iter())
+            .map(|(_, _, _, mov)| (mov, -ai::score(&self.after(mov), depth.max(1) - 1)))
+            .collect();
+        ranked.sort_by_key(|&(_, score)| -score);
+        ranked
+    }
+
+    /// The position after `mov`, with an empty history.
+    fn after(&self, mov: Move) -> Abalone {
+        let mut game = self.clone_position();
+        game.apply_move(mov);
+        game.turn = game.turn.opposite();
+        game
+    }
+
+    /// Plays the `line` of selections and directions on a copy of the position
+    /// and returns the resulting [`ai::evaluate`] score of `perspective`.
+    ///
+    /// On failure the index of the first illegal move is returned alongside
+    /// the error.
+    pub fn evaluate_line(
+        &self,
+        line: &[(Pos2, Pos2, Dir)],
+        perspective: Color,
+    ) -> Result<i32, (usize, Error)> {
+        let mut game = self.clone_position();
+        for (i, &(first, last, dir)) in line.iter().enumerate() {
+            let mov = game
+                .check_game_move([first, last], dir)
+                .map_err(|e| (i, e))?;
+            game.submit_move(mov);
+        }
+        Ok(ai::evaluate(&game, perspective))
+    }
+
+    /// The color that has pushed off [`NUM_BALLS_TO_WIN`] opposing balls.
+    pub fn winner(&self) -> Option<Color> {
+        let (black_score, white_score) = self.scores();
+        if black_score >= NUM_BALLS_TO_WIN {
+            Some(Color::Black)
+        } else if white_score >= NUM_BALLS_TO_WIN {
+            Some(Color::White)
+        } else {
+            None
+        }
+    }
+
+    /// The result of the game so far, taking the [`Rules::move_limit`] into
+    /// account. If the game is drawn, the scores can be used as a tie-break.
+    pub fn standing(&self) -> Standing {
+        if let Some(color) = self.winner() {
+            return Standing::Win(color);
+        }
+        if self.draw_agreed {
+            return Standing::DrawByAgreement;
+        }
+
+        match self.rules.move_limit {
+            Some(limit) if self.ply() >= limit => {
+                let (black, white) = self.scores();
+                Standing::DrawByLimit { black, white }
+            }
+            _ => Standing::Ongoing,
+        }
+    }
+
+    /// The board packed into one byte per cell in row-major order, `0` for an
+    /// empty or invalid cell, `1` for a black and `2` for a white ball.
+    pub fn snapshot(&self) -> [u8; (SIZE * SIZE) as usize] {
+        let mut snapshot = [0; (SIZE * SIZE) as usize];
+        for (x, y, c) in self.iter() {
+            if let Some(c) = c {
+                snapshot[(y * SIZE + x) as usize] = c as u8 + 1;
+            }
+        }
+        snapshot
+    }
+
+    /// The [`Self::snapshot`] of the start position followed by the snapshots
+    /// after each played move, up to the current move.
+    ///
+    /// If moves were dropped from the history, the earliest position that can
+    /// be restored is used instead of the start position.
+    ///
+    /// This replays the whole game, so it isn't meant to be called every frame.
+    pub fn snapshots(&self) -> Vec<[u8; (SIZE * SIZE) as usize]> {
+        self.replay().map(|(_, game)| game.snapshot()).collect()
+    }
+
+    /// Replaces every black ball with a white one and vice versa, and passes
+    /// the turn to the other color. Since the scores are derived from the
+    /// balls on the board, they are swapped as well.
+    pub fn swap_colors(&mut self) {
+        for (_, c) in self.iter_mut() {
+            *c = c.map(|c| c.opposite());
+        }
+        self.turn = self.turn.opposite();
+    }
+
+    /// A copy of the balls, turn and rules, with an empty move history. An
+    /// agreed draw is kept, so a copy of a finished game stays finished.
+    pub fn clone_position(&self) -> Abalone {
+        Abalone {
+            balls: self.balls,
+            moves: Vec::new(),
+            move_idx: 0,
+            dropped_moves: 0,
+            turn: self.turn,
+            rules: self.rules,
+            draw_agreed: self.draw_agreed,
+            events: Vec::new(),
+        }
+    }
+
+    /// The position after the first `ply` played moves of the history, with an
+    /// empty history, see [`Self::clone_position`]. Moves dropped from the
+    /// history aren't counted, and `ply` is capped at the current move.
+    pub fn board_at(&self, ply: usize) -> Abalone {
+        let mut game = self.clone_position();
+        let ply = ply.min(self.move_idx);
+        for &mov in self.moves[ply..self.move_idx].iter().rev() {
+            game.unapply_move(mov);
+            game.turn = game.turn.opposite();
+        }
+        game
+    }
+
+    /// Every position of the history up to the current move with its
+    /// [`Self::ply`], starting with the earliest position that can be restored
+    /// and ending with the current one. The positions have an empty history,
+    /// see [`Self::board_at`].
+    pub fn replay(&self) -> impl Iterator<Item = (usize, Abalone)> + '_ {
+        let mut game = self.board_at(0);
+        let moves = &self.moves[..self.move_idx];
+        (0..=moves.len()).map(move |i| {
+            if i > 0 {
+                game.apply_move(moves[i - 1]);
+                game.turn = game.turn.opposite();
+            }
+            (self.dropped_moves + i, game.clone())
+        })
+    }
+
+    /// How many times the current balls and turn occurred in the history up
+    /// to the current move, including the current position itself. Moves
+    /// dropped from the history aren't taken into account.
+    pub fn repetition_count(&self) -> u8 {
+        let mut game = self.clone_position();
+        let mut count: u8 = 1;
+        for &mov in self.moves[..self.move_idx].iter().rev() {
+            game.unapply_move(mov);
+            game.turn = game.turn.opposite();
+            if game.turn == self.turn && game.balls == self.balls {
+                count = count.saturating_add(1);
+            }
+        }
+        count
+    }
+
+    /// Whether the current position occurred at least three times, see
+    /// [`Self::repetition_count`].
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// Whether both boards have the same balls. Unlike `==`, this ignores the
+    /// color on turn, as well as the move history and rules.
+    pub fn equals_ignoring_turn(&self, other: &Abalone) -> bool {
+        self.balls == other.balls
+    }
+
+    /// The start position the balls are laid out in, ignoring the turn and
+    /// move history.
+    pub fn is_starting_position(&self) -> Option<StartPosition> {
+        StartPosition::ALL
+            .into_iter()
+            .find(|&start| Abalone::with_start_position(start).balls == self.balls)
+    }
+
+    /// Ends the game in a draw both players agreed to, without a move. A game
+    /// that was already won stays won.
+    pub fn agree_draw(&mut self) {
+        let outcome = self.outcome();
+        self.draw_agreed = true;
+        self.record_outcome(outcome);
+    }
+
+    /// The result of the game, if it's finished.
+    pub fn outcome(&self) -> Option<Outcome> {
+        match self.standing() {
+            Standing::Win(color) => Some(Outcome::Win(color)),
+            Standing::Ongoing => None,
+            Standing::DrawByLimit { .. } => Some(Outcome::Draw(DrawReason::MoveLimit)),
+            Standing::DrawByAgreement => Some(Outcome::Draw(DrawReason::Agreement)),
+        }
+    }
+
+    /// The status of the game, derived from the board and the history, so it
+    /// stays correct after undoing and redoing moves.
+    pub fn status(&self) -> GameStatus {
+        match self.outcome() {
+            None => GameStatus::Ongoing { turn: self.turn },
+            Some(Outcome::Win(winner)) => GameStatus::Win { winner },
+            Some(Outcome::Draw(_)) => GameStatus::Draw,
+        }
+    }
+
+    /// A hash of the balls and the turn, ignoring the move history.
+    ///
+    /// The keys are generated once from [`ZOBRIST_SEED`] with splitmix64, two
+    /// per cell for black and white in row-major order, followed by the key
+    /// of black being on turn.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = match self.turn {
+            Color::Black => ZOBRIST_KEYS.turn,
+            Color::White => 0,
+        };
+        for (x, y, c) in self.iter() {
+            if let Some(c) = c {
+                hash ^= ZOBRIST_KEYS.balls[y as usize][x as usize][c as usize];
+            }
+        }
+        hash
+    }
+
+    /// The smallest [`Self::zobrist_hash`] of the 12 symmetric positions, so
+    /// positions that are rotations or reflections of each other share it.
+    ///
+    /// The positions are rotated with [`Pos2::rotate`], once without and once
+    /// with reflecting across the axis through `(0, 0)` and `(8, 8)` first,
+    /// which swaps the x and y coordinates. The turn is kept.
+    pub fn canonical_hash(&self) -> u64 {
+        let turn = match self.turn {
+            Color::Black => ZOBRIST_KEYS.turn,
+            Color::White => 0,
+        };
+        let mut min = u64::MAX;
+        for reflected in [false, true] {
+            for steps in 0..6 {
+                let mut hash = turn;
+                for (x, y, c) in self.iter() {
+                    let Some(c) = c else {
+                        continue;
+                    };
+                    let pos = match reflected {
+                        true => Pos2 { x: y, y: x },
+                        false => Pos2 { x, y },
+                    };
+                    let Pos2 { x, y } = pos.rotate(steps);
+                    hash ^= ZOBRIST_KEYS.balls[y as usize][x as usize][c as usize];
+                }
+                min = min.min(hash);
+            }
+        }
+        min
+    }
+
+    /// A stable key of the position, meant for [`HashMap`](std::collections::HashMap)s
+    /// of positions. Positions with the same balls and turn share the same key,
+    /// regardless of the moves that led to them, see [`Self::zobrist_hash`].
+    pub fn position_key(&self) -> u64 {
+        self.zobrist_hash()
+    }
+
+    /// All legal moves of the current player, see [`Self::legal_moves_iter`].
+    pub fn legal_moves(&self) -> Vec<(Pos2, Pos2, Dir, Move)> {
+        self.legal_moves_iter().collect()
+    }
+
+    /// Lazily yields all legal moves of the current player as the selection,
+    /// the direction and the resulting move.
+    ///
+    /// Every move is yielded exactly once, moves in the direction of the
+    /// selected line use the last ball as selection, the other balls in front
+    /// of it are inferred.
+    pub fn legal_moves_iter(&self) -> impl Iterator<Item = (Pos2, Pos2, Dir, Move)> + '_ {
+        board_cells()
+            .filter(|&p| self[p] == Some(self.turn))
+            .flat_map(|first| {
+                let inline = DIRS.into_iter().map(move |dir| (first, first, dir));
+                let sideward = [UNIT_X, UNIT_Y, UNIT_Z].into_iter().flat_map(move |axis| {
+                    (1..=2).flat_map(move |i| {
+                        let last = first + axis * i;
+                        DIRS.into_iter()
+                            .filter(move |d| !d.vec().is_parallel(axis))
+                            .map(move |dir| (first, last, dir))
+                    })
+                });
+                inline.chain(sideward)
+            })
+            .filter_map(|(first, last, dir)| {
+                let mov = self.check_move([first, last], dir).ok()?;
+                Some((first, last, dir, mov))
+            })
+    }
+
+    /// The distinct positions, without history, after each legal move of the
+    /// current player.
+    pub fn successors(&self) -> Vec<Abalone> {
+        let mut successors = Vec::new();
+        let mut seen = HashSet::new();
+        for (_, _, _, mov) in self.legal_moves_iter() {
+            let game = self.after(mov);
+            // the balls and turn themselves, since hashes could collide
+            if seen.insert((game.balls, game.turn)) {
+                successors.push(game);
+            }
+        }
+        successors
+    }
+
+    /// The shortest sequence of legal moves, with the players taking turns,
+    /// that transforms the board into the one of `target`, ignoring its turn
+    /// and history. Returns [`None`] if it can't be reached within
+    /// `max_plies`, meant for puzzles like reaching a shape in 3 moves.
+    ///
+    /// The search is breadth first, so the limit should stay small.
+    pub fn solves(&self, target: &Abalone, max_plies: u32) -> Option<Vec<Move>> {
+        let start = self.clone_position();
+        let mut seen = HashSet::from([(start.balls, start.turn)]);
+        let mut queue = VecDeque::from([(start, Vec::new())]);
+        while let Some((game, moves)) = queue.pop_front() {
+            if game.equals_ignoring_turn(target) {
+                return Some(moves);
+            }
+            if moves.len() as u32 >= max_plies {
+                continue;
+            }
+
+            for (_, _, _, mov) in game.legal_moves_iter() {
+                let next = game.after(mov);
+                if seen.insert((next.balls, next.turn)) {
+                    let mut moves = moves.clone();
+                    moves.push(mov);
+                    queue.push_back((next, moves));
+                }
+            }
+        }
+        None
+    }
+
+    /// The number of legal moves of the current player, without collecting
+    /// them.
+    pub fn legal_move_count(&self) -> usize {
+        self.legal_moves_iter().count()
+    }
+
+    /// Counts the move sequences of `depth` plies from the current position,
+    /// meant for validating the move generation. Depth 0 counts as 1.
+    ///
+    /// The moves are applied and unapplied in place, the position and history
+    /// are unchanged afterwards. Positions with a winner are searched further
+    /// like any other.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        // one move buffer per searched ply, reused by all positions of it
+        let mut buffers = vec![Vec::new(); depth.saturating_sub(1) as usize];
+        self.perft_with(depth, &mut buffers)
+    }
+
+    fn perft_with(&mut self, depth: u32, buffers: &mut [Vec<Move>]) -> u64 {
+        match depth {
+            0 => return 1,
+            1 => return self.legal_move_count() as u64,
+            _ => (),
+        }
+
+        let (moves, buffers) = buffers.split_first_mut().expect("a buffer per ply");
+        moves.clear();
+        moves.extend(self.legal_moves_iter().map(|(_, _, _, mov)| mov));
+        let mut count = 0;
+        for &mov in moves.iter() {
+            self.apply_move(mov);
+            self.turn = self.turn.opposite();
+            count += self.perft_with(depth - 1, buffers);
+            self.turn = self.turn.opposite();
+            self.unapply_move(mov);
+        }
+        count
+    }
+
+    /// The first move of [`Self::legal_moves_iter`], a fast but in no way
+    /// strategic choice, e.g. for filler opponents.
+    pub fn first_legal_move(&self) -> Option<Move> {
+        self.legal_moves_iter().next().map(|(_, _, _, mov)| mov)
+    }
+
+    /// Whether the current player has at least one legal move.
+    pub fn has_legal_move(&self) -> bool {
+        self.legal_moves_iter().next().is_some()
+    }
+
+    /// Whether the current player is obliged to move, passing is only
+    /// conceivable if there isn't any legal move.
+    pub fn must_move(&self) -> bool {
+        self.has_legal_move()
+    }
+
+    /// All directions in which the selection can be moved, with the resulting
+    /// move.
+    pub fn legal_dirs(&self, selection: [Pos2; 2]) -> Vec<(Dir, Move)> {
+        DIRS.into_iter()
+            .filter_map(|dir| Some((dir, self.check_move(selection, dir).ok()?)))
+            .collect()
+    }
+
+    /// The number of opposing balls `mov` pushes, on the board before the move
+    /// is applied.
+    pub fn pushed_count(&self, mov: Move) -> u8 {
+        match mov {
+            Move::PushedOff { first, last } | Move::PushedAway { first, last } => {
+                let vec = last - first;
+                let norm = vec.norm();
+                let color = self[first];
+                (0..=vec.mag())
+                    .filter(|&i| self[first + norm * i] != color)
+                    .count() as u8
+            }
+            Move::Moved { .. } | Move::Pass => 0,
+        }
+    }
+
+    /// The change in material `mov` causes for the side making it, `1` if it
+    /// pushes off an opposing ball and `0` otherwise. Legal moves never lose
+    /// an own ball.
+    pub fn material_effect(&self, mov: Move) -> i8 {
+        match mov {
+            Move::PushedOff { .. } => 1,
+            Move::PushedAway { .. } | Move::Moved { .. } | Move::Pass => 0,
+        }
+    }
+
+    /// Checks the move like [`Self::check_move`], but rejects any move once
+    /// the game is won or drawn, see [`Self::outcome`]. Since the winner is
+    /// derived from the board, undoing the winning move allows moves again.
+    pub fn check_game_move(&self, selection: [Pos2; 2], dir: Dir) -> Result<Move, Error> {
+        if let Some(outcome) = self.outcome() {
+            return Err(Error::GameOver(outcome));
+        }
+        self.check_move(selection, dir)
+    }
+
+    /// Checks passing the turn, which is only allowed if the current player
+    /// has no legal move, see [`Self::must_move`]. Like any other move, a pass
+    /// is rejected once the game is finished.
+    pub fn check_pass(&self) -> Result<Move, Error> {
+        if let Some(outcome) = self.outcome() {
+            return Err(Error::GameOver(outcome));
+        }
+        if self.must_move() {
+            return Err(Error::PassNotAllowed);
+        }
+        Ok(Move::Pass)
+    }
+
+    /// Checks and submits a move, but only if the current position still has
+    /// the hash the move was proposed for. This prevents applying stale moves.
+    pub fn apply_if_unchanged(
+        &mut self,
+        expected_hash: u64,
+        selection: [Pos2; 2],
+        dir: Dir,
+    ) -> Result<Move, ApplyError> {
+        let actual = self.zobrist_hash();
+        if actual != expected_hash {
+            return Err(ApplyError::PositionChanged {
+                expected: expected_hash,
+                actual,
+            });
+        }
+
+        let mov = self.check_game_move(selection, dir)?;
+        self.submit_move(mov);
+        Ok(mov)
+    }
+
+    /// Counts the contiguous balls of the opposite of `color`, starting at
+    /// `from` and following `dir`, and what the run ends at.
+    pub fn opposing_run(&self, from: Pos2, dir: Dir, color: Color) -> (u8, RunEnd) {
+        let opposing_color = color.opposite();
+        let mut count = 0;
+        let mut pos = from;
+        loop {
+            match self.get(pos) {
+                Some(&Some(c)) if c == opposing_color => count += 1,
+                Some(Some(_)) => return (count, RunEnd::Own),
+                Some(None) => return (count, RunEnd::Empty),
+                None => return (count, RunEnd::Edge),
+            }
+            pos = pos + dir.vec();
+        }
+    }
+
+    /// The balls of `color` that the opponent could push off with their next
+    /// move.
+    pub fn threatened(&self, color: Color) -> Vec<Pos2> {
+        let mut game = self.clone_position();
+        game.turn = color.opposite();
+
+        let mut threatened = Vec::new();
+        for (_, _, _, mov) in game.legal_moves_iter() {
+            if let Move::PushedOff { last, .. } = mov {
+                if !threatened.contains(&last) {
+                    threatened.push(last);
+                }
+            }
+        }
+        threatened
+    }
+
+    /// The pushes of 3 balls of `color` against 1 or 2 opposing balls, as the
+    /// selection and direction, if `color` were on turn.
+    pub fn find_sumito_setups(&self, color: Color) -> Vec<(Pos2, Pos2, Dir)> {
+        let mut game = self.clone_position();
+        game.turn = color;
+
+        let mut setups = Vec::new();
+        for (first, last, dir, mov) in game.legal_moves_iter() {
+            let (Move::PushedOff {
+                first: tail,
+                last: head,
+            }
+            | Move::PushedAway {
+                first: tail,
+                last: head,
+            }) = mov
+            else {
+                continue;
+            };
+            let num_balls = (head - tail).mag() as u8 + 1;
+            if num_balls - game.pushed_count(mov) == 3 {
+                setups.push((first, last, dir));
+            }
+        }
+        setups
+    }
+
+    /// Whether every legal move of the current player leaves at least one of
+    /// their balls [`Self::threatened`] by the opponent.
+    pub fn inevitable_capture(&self) -> bool {
+        let mut has_moves = false;
+        for (_, _, _, mov) in self.legal_moves_iter() {
+            has_moves = true;
+
+            if self.after(mov).threatened(self.turn).is_empty() {
+                return false;
+            }
+        }
+        has_moves
+    }
+
+    /// Validates and submits all moves in order, stopping at the first move
+    /// that isn't consistent with the board. The moves before it stay applied.
+    pub fn apply_many(&mut self, moves: &[Move]) -> Result<(), (usize, ApplyError)> {
+        for (i, &mov) in moves.iter().enumerate() {
+            self.validate_move(mov).map_err(|e| (i, e))?;
+            self.submit_move(mov);
+        }
+        Ok(())
+    }
+
+    /// Applies the move and records it in the history. Once the game is won
+    /// or drawn no more moves are accepted, and this does nothing.
     pub fn submit_move(&mut self, mov: Move) {
+        let outcome = self.outcome();
+        if outcome.is_some() {
+            return;
+        }
         self.apply_move(mov);
+        self.push_event(GameEvent::Move(mov));
 
         self.turn = self.turn.opposite();
         self.moves.drain(self.move_idx..);
         self.moves.push(mov);
         self.move_idx += 1;
+
+        if let Some(max) = self.rules.max_history {
+            let excess = self.moves.len().saturating_sub(max);
+            self.moves.drain(..excess);
+            self.move_idx -= excess;
+            self.dropped_moves += excess;
+        }
+
+        self.record_outcome(outcome);
+    }
+
+    /// Every change of the game in order, meant to be replayed to spectators
+    /// that join late. Events are only ever appended, unlike the moves they
+    /// aren't limited by [`Rules::max_history`].
+    pub fn event_log(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    fn push_event(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Records the outcome if the game was just finished, `previous` is the
+    /// outcome before the change.
+    fn record_outcome(&mut self, previous: Option<Outcome>) {
+        if let Some(outcome) = self.outcome() {
+            if Some(outcome) != previous {
+                self.push_event(GameEvent::Outcome(outcome));
+            }
+        }
+    }
+
+    /// The number of moves played up to the current one, including the ones
+    /// dropped from the history.
+    pub fn ply(&self) -> usize {
+        self.dropped_moves + self.move_idx
+    }
+
+    /// Passes the turn to the opponent without moving any balls.
+    ///
+    /// This isn't part of the standard rules, where a player has to move if
+    /// able to, it's meant for debugging and adjudication. The pass is recorded
+    /// as a [`Move::Pass`] and can be undone like any other move, but it isn't
+    /// checked, see [`Self::check_pass`].
+    pub fn pass_turn(&mut self) {
+        self.submit_move(Move::Pass);
     }
 
     pub fn can_undo(&self) -> bool {
@@ -725,6 +2147,7 @@ impl Abalone {
         self.move_idx -= 1;
         let mov = self.moves[self.move_idx];
         self.unapply_move(mov);
+        self.push_event(GameEvent::Undo);
     }
 
     pub fn redo_move(&mut self) {
@@ -732,10 +2155,40 @@ impl Abalone {
             return;
         }
 
+        let outcome = self.outcome();
         self.turn = self.turn.opposite();
         let mov = self.moves[self.move_idx];
         self.move_idx += 1;
-        self.apply_move(mov)
+        self.apply_move(mov);
+        self.push_event(GameEvent::Redo);
+        self.record_outcome(outcome);
+    }
+
+    /// Undoes all moves of the history at once, they can still be redone.
+    /// Moves dropped from the history can't be undone.
+    pub fn reset(&mut self) {
+        if self.move_idx == 0 {
+            return;
+        }
+
+        while self.move_idx > 0 {
+            self.turn = self.turn.opposite();
+            self.move_idx -= 1;
+            self.unapply_move(self.moves[self.move_idx]);
+        }
+        self.push_event(GameEvent::Reset);
+    }
+
+    /// Undoes or redoes moves until `idx` moves of the history are played,
+    /// `idx` is capped at the length of the history.
+    pub fn jump_to(&mut self, idx: usize) {
+        let idx = idx.min(self.moves.len());
+        while self.move_idx > idx {
+            self.undo_move();
+        }
+        while self.move_idx < idx {
+            self.redo_move();
+        }
     }
 
     fn apply_move(&mut self, mov: Move) {
@@ -776,6 +2229,7 @@ impl Abalone {
                     self[pos] = None;
                 }
             }
+            Move::Pass => (),
         }
     }
 
@@ -817,6 +2271,7 @@ impl Abalone {
                     self[pos] = None;
                 }
             }
+            Move::Pass => (),
         }
     }
 }
@@ -825,3 +2280,87 @@ pub fn is_in_bounds(pos: impl Into<Pos2>) -> bool {
     let Pos2 { x, y } = pos.into();
     (0..SIZE).contains(&x) && (0..SIZE).contains(&y) && x - y < 5 && y - x < 5
 }
+
+/// Moves the end of the `selection` closest to `pos` onto it, where `pos` is
+/// neither end of the selection. If `pos` lies between both ends, this shrinks
+/// the selection instead.
+///
+/// Only the geometry is checked, the balls themselves are checked by
+/// [`Abalone::check_selection`].
+pub fn extend_selection([start, end]: [Pos2; 2], pos: Pos2) -> Result<[Pos2; 2], ExtendError> {
+    let sel_vec = end - start;
+    let start_vec = pos - start;
+    let end_vec = pos - end;
+    let collinear = start_vec.is_multiple_of_unit_vec()
+        && (sel_vec == Vec2::ZERO || start_vec.is_parallel(sel_vec));
+    if !collinear {
+        return Err(ExtendError::NotCollinear);
+    }
+
+    let selection = if start_vec.mag() < end_vec.mag() {
+        [pos, end]
+    } else {
+        [start, pos]
+    };
+    if (selection[1] - selection[0]).mag() < 3 {
+        Ok(selection)
+    } else if start_vec.mag().min(end_vec.mag()) == 1 {
+        Err(ExtendError::WouldExceedThree)
+    } else {
+        Err(ExtendError::NotAdjacent)
+    }
+}
+
+struct ZobristKeys {
+    balls: [[[u64; 2]; SIZE as usize]; SIZE as usize],
+    turn: u64,
+}
+
+impl ZobristKeys {
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut balls = [[[0; 2]; SIZE as usize]; SIZE as usize];
+        let mut y = 0;
+        while y < SIZE as usize {
+            let mut x = 0;
+            while x < SIZE as usize {
+                balls[y][x][0] = splitmix64(&mut state);
+                balls[y][x][1] = splitmix64(&mut state);
+                x += 1;
+            }
+            y += 1;
+        }
+        let turn = splitmix64(&mut state);
+
+        Self { balls, turn }
+    }
+}
+
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Whether the position is on the outermost ring of the board, meaning at least
+/// one of its neighbors is off the board.
+pub fn is_edge(pos: impl Into<Pos2>) -> bool {
+    let pos = pos.into();
+    is_in_bounds(pos)
+        && [UNIT_X, UNIT_Y, UNIT_Z]
+            .into_iter()
+            .any(|v| !is_in_bounds(pos + v) || !is_in_bounds(pos - v))
+}
+
+/// All positions on the outermost ring of the board, in row-major order.
+pub fn edge_cells() -> impl Iterator<Item = Pos2> {
+    board_cells().filter(|&p| is_edge(p))
+}
+
+/// All 61 positions on the board, in row-major order, without iterating over
+/// the cells outside of it.
+pub fn board_cells() -> impl Iterator<Item = Pos2> {
+    (0..SIZE).flat_map(|y| ((y - 4).max(0)..(y + 5).min(SIZE)).map(move |x| Pos2 { x, y }))
+}
@@ -0,0 +1,163 @@
+//! Conventional Abalone coordinates.
+//!
+//! Rows are labeled with the letters `A` to `I` and diagonals with the numbers
+//! `1` to `9`, so that the corner at `(0, 0)` is `A1` and the one at `(8, 8)`
+//! is `I9`:
+//! ```md
+//!         I5 I6 I7 I8 I9
+//!        H4 H5 H6 H7 H8 H9
+//!       G3 G4 G5 G6 G7 G8 G9
+//!      F2 F3 F4 F5 F6 F7 F8 F9
+//!     E1 E2 E3 E4 E5 E6 E7 E8 E9
+//!      D1 D2 D3 D4 D5 D6 D7 D8
+//!       C1 C2 C3 C4 C5 C6 C7
+//!        B1 B2 B3 B4 B5 B6
+//!         A1 A2 A3 A4 A5
+//! ```
+//!
+//! Some conventions label the board from the opposite side instead, see
+//! [`Orientation`].
+
+use crate::{is_in_bounds, Abalone, Dir, Error, Move, Pos2, Vec2, SIZE};
+
+/// Which corner of the board the cell `A1` is in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Orientation {
+    /// `A1` is at `(0, 0)`, on the side black starts on.
+    #[default]
+    Standard,
+    /// The board is labeled from the opposite side, `A1` is at `(8, 8)`, on
+    /// the side white starts on.
+    Rotated,
+}
+
+impl Orientation {
+    /// Maps positions between the [`Orientation::Standard`] labels and these,
+    /// the mapping is its own inverse.
+    fn map(self, pos: Pos2) -> Pos2 {
+        match self {
+            Orientation::Standard => pos,
+            Orientation::Rotated => Pos2 {
+                x: SIZE - 1 - pos.x,
+                y: SIZE - 1 - pos.y,
+            },
+        }
+    }
+}
+
+impl Pos2 {
+    /// The conventional notation of this cell, e.g. `C5`.
+    pub fn to_notation(&self) -> String {
+        self.to_notation_with(Orientation::Standard)
+    }
+
+    /// The notation of this cell, labeled according to the `orientation`.
+    pub fn to_notation_with(&self, orientation: Orientation) -> String {
+        let pos = orientation.map(*self);
+        let row = (b'A' + pos.y as u8) as char;
+        let diagonal = pos.x + 1;
+        format!("{row}{diagonal}")
+    }
+
+    /// Parses the conventional notation of a cell, returns [`None`] if the
+    /// string is malformed or the cell isn't on the board.
+    pub fn from_notation(s: &str) -> Option<Pos2> {
+        Pos2::from_notation_with(s, Orientation::Standard)
+    }
+
+    /// Parses the notation of a cell labeled according to the `orientation`,
+    /// see [`Self::from_notation`].
+    pub fn from_notation_with(s: &str, orientation: Orientation) -> Option<Pos2> {
+        let &[row, diagonal] = s.as_bytes() else {
+            return None;
+        };
+        let row = row.to_ascii_uppercase();
+        if !(b'A'..=b'I').contains(&row) || !(b'1'..=b'9').contains(&diagonal) {
+            return None;
+        }
+
+        let pos = Pos2 {
+            x: (diagonal - b'1') as i8,
+            y: (row - b'A') as i8,
+        };
+        is_in_bounds(pos).then_some(orientation.map(pos))
+    }
+}
+
+impl Move {
+    /// Inline moves are written as the trailing ball and the cell it moves to,
+    /// e.g. `A1B2`, broadside moves as both ends of the selection followed by
+    /// the cell the first ball moves to, e.g. `C3C5D3`. A [`Move::Pass`] is
+    /// written as `pass`.
+    pub fn to_notation(&self) -> String {
+        let (first, last, dir) = match *self {
+            Move::PushedOff { first, last } | Move::PushedAway { first, last } => {
+                (first, first, (last - first).norm())
+            }
+            Move::Moved { dir, first, last } => (first, last, dir.vec()),
+            Move::Pass => return "pass".to_string(),
+        };
+
+        let vec = last - first;
+        if vec == Vec2::ZERO || vec.is_parallel(dir) {
+            // only the trailing ball of inline moves
+            format!("{}{}", first.to_notation(), (first + dir).to_notation())
+        } else {
+            let [first, last, dest] = [first, last, first + dir].map(|p| p.to_notation());
+            format!("{first}{last}{dest}")
+        }
+    }
+}
+
+impl Abalone {
+    /// The notation of the last `n` played moves up to the current one, the
+    /// most recent move last.
+    pub fn recent_notation(&self, n: usize) -> Vec<String> {
+        let start = self.move_idx.saturating_sub(n);
+        (self.moves[start..self.move_idx].iter())
+            .map(|m| m.to_notation())
+            .collect()
+    }
+
+    /// Parses a move written by [`Move::to_notation`] and checks it against
+    /// the current board like [`Self::check_game_move`]. A `pass` is checked
+    /// like by [`Self::check_pass`].
+    pub fn parse_move(&self, s: &str) -> Result<Move, Error> {
+        if s.trim().eq_ignore_ascii_case("pass") {
+            return self.check_pass();
+        }
+        let Some((selection, dir)) = parse_move_text(s) else {
+            return Err(Error::InvalidNotation(s.to_string()));
+        };
+        self.check_game_move(selection, dir)
+    }
+}
+
+/// Parses the selection and direction of a move.
+///
+/// Inline moves are written as the trailing ball and the cell it moves to,
+/// e.g. `A1B2`, broadside moves as both ends of the selection followed by the
+/// cell the first ball moves to, e.g. `C3C5D3`. The cells may be separated by
+/// a `-`.
+pub(crate) fn parse_move_text(s: &str) -> Option<([Pos2; 2], Dir)> {
+    let mut cells = [Pos2::ZERO; 3];
+    let mut num_cells = 0;
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        if num_cells == cells.len() || !rest.is_char_boundary(2) {
+            return None;
+        }
+        let (cell, remainder) = rest.split_at(2);
+        cells[num_cells] = Pos2::from_notation(cell)?;
+        num_cells += 1;
+        rest = remainder.strip_prefix('-').unwrap_or(remainder);
+    }
+
+    let (selection, dest) = match num_cells {
+        2 => ([cells[0]; 2], cells[1]),
+        3 => ([cells[0], cells[1]], cells[2]),
+        _ => return None,
+    };
+    let dir = (dest - selection[0]).unit_vec()?;
+    Some((selection, dir))
+}
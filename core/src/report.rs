@@ -0,0 +1,56 @@
+use crate::{Abalone, Color, SIZE};
+
+/// A summary of a game, meant to be shown once it's finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameReport {
+    /// The number of moves played, see [`Abalone::ply`].
+    pub plies: usize,
+    /// The number of opposing balls black pushed off.
+    pub black_captures: u8,
+    /// The number of opposing balls white pushed off.
+    pub white_captures: u8,
+    pub winner: Option<Color>,
+    /// The most opposing balls pushed by a single move.
+    pub longest_push: u8,
+    /// The [`Abalone::snapshot`] of the final board.
+    pub final_board: [u8; (SIZE * SIZE) as usize],
+}
+
+impl Abalone {
+    /// Summarizes the game up to the current move.
+    ///
+    /// The moves are replayed to find the longest push, moves dropped from the
+    /// history aren't taken into account.
+    pub fn report(&self) -> GameReport {
+        let longest_push = (self.replay())
+            .zip(&self.moves[..self.move_idx])
+            .map(|((_, game), &mov)| game.pushed_count(mov))
+            .max()
+            .unwrap_or(0);
+
+        let (black_captures, white_captures) = self.scores();
+        GameReport {
+            plies: self.ply(),
+            black_captures,
+            white_captures,
+            winner: self.winner(),
+            longest_push,
+            final_board: self.snapshot(),
+        }
+    }
+
+    /// How many positions of the game each cell was occupied in, indexed by
+    /// `[y][x]`. All positions from the start of the history up to the
+    /// current move are counted, including both of them.
+    pub fn occupancy_over_game(&self) -> [[u16; SIZE as usize]; SIZE as usize] {
+        let mut occupancy = [[0; SIZE as usize]; SIZE as usize];
+        for (_, game) in self.replay() {
+            for (x, y, c) in game.iter() {
+                if c.is_some() {
+                    occupancy[y as usize][x as usize] += 1;
+                }
+            }
+        }
+        occupancy
+    }
+}
@@ -0,0 +1,152 @@
+//! Replay scripts, a compact line based format for recording games by hand.
+//!
+//! Every line contains one of:
+//! - a move prefixed by the color that plays it, e.g. `W G5-F4` or `B C3C5D3`,
+//!   see [`crate::notation`] for the move format
+//! - `undo` or `redo`
+//! - nothing, or a comment starting with `#`
+//!
+//! ```md
+//! # opening
+//! W G5-F4
+//! B C3-D3
+//! undo
+//! B C3C5D3 # broadside
+//! ```
+//!
+//! A collection contains multiple scripts separated by lines consisting of
+//! `---`, see [`load_collection`].
+
+use std::fmt;
+
+use crate::notation::parse_move_text;
+use crate::{Abalone, Color, Error};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The line couldn't be parsed.
+    Syntax { line: usize },
+    /// The move was prefixed with the color that isn't on turn.
+    WrongColor { line: usize, expected: Color },
+    /// The move isn't legal on the current board.
+    Illegal { line: usize, error: Error },
+    /// There was no move to undo.
+    NothingToUndo { line: usize },
+    /// There was no move to redo.
+    NothingToRedo { line: usize },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Syntax { line } => write!(f, "Line {line}: invalid syntax"),
+            ScriptError::WrongColor { line, expected } => {
+                write!(f, "Line {line}: expected a move by {expected}")
+            }
+            ScriptError::Illegal { line, error } => write!(f, "Line {line}: {error}"),
+            ScriptError::NothingToUndo { line } => write!(f, "Line {line}: nothing to undo"),
+            ScriptError::NothingToRedo { line } => write!(f, "Line {line}: nothing to redo"),
+        }
+    }
+}
+
+/// A game of a collection failed to load, see [`load_collection`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollectionError {
+    /// The index of the game in the collection, starting at 0.
+    pub game: usize,
+    /// The error, with the line number counted from the start of the game.
+    pub error: ScriptError,
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}: {}", self.game, self.error)
+    }
+}
+
+/// Loads a collection of replay scripts separated by `---` lines, each played
+/// from the standard start position. Sections without any lines besides
+/// whitespace are skipped.
+pub fn load_collection(s: &str) -> Result<Vec<Abalone>, CollectionError> {
+    let mut sections = vec![String::new()];
+    for line in s.lines() {
+        if line.trim() == "---" {
+            sections.push(String::new());
+            continue;
+        }
+        let section = sections.last_mut().expect("there should be a section");
+        section.push_str(line);
+        section.push('\n');
+    }
+
+    let sections = sections.iter().filter(|s| !s.trim().is_empty());
+    sections
+        .enumerate()
+        .map(|(game, script)| {
+            let mut abalone = Abalone::new();
+            abalone
+                .run_script(script)
+                .map_err(|error| CollectionError { game, error })?;
+            Ok(abalone)
+        })
+        .collect()
+}
+
+impl Abalone {
+    /// Runs a replay script on the current game.
+    ///
+    /// Lines are numbered starting at 1. Execution stops at the first
+    /// offending line, the lines before it remain applied.
+    pub fn run_script(&mut self, script: &str) -> Result<(), ScriptError> {
+        for (idx, text) in script.lines().enumerate() {
+            let line = idx + 1;
+            let text = match text.split_once('#') {
+                Some((code, _comment)) => code.trim(),
+                None => text.trim(),
+            };
+
+            match text {
+                "" => (),
+                "undo" => {
+                    if !self.can_undo() {
+                        return Err(ScriptError::NothingToUndo { line });
+                    }
+                    self.undo_move();
+                }
+                "redo" => {
+                    if !self.can_redo() {
+                        return Err(ScriptError::NothingToRedo { line });
+                    }
+                    self.redo_move();
+                }
+                _ => {
+                    let Some((color, mov)) = text.split_once(' ') else {
+                        return Err(ScriptError::Syntax { line });
+                    };
+                    let color = match color {
+                        "B" | "b" => Color::Black,
+                        "W" | "w" => Color::White,
+                        _ => return Err(ScriptError::Syntax { line }),
+                    };
+                    let Some((selection, dir)) = parse_move_text(mov) else {
+                        return Err(ScriptError::Syntax { line });
+                    };
+
+                    if color != self.turn {
+                        return Err(ScriptError::WrongColor {
+                            line,
+                            expected: self.turn,
+                        });
+                    }
+                    let mov = self
+                        .check_game_move(selection, dir)
+                        .map_err(|error| ScriptError::Illegal { line, error })?;
+                    self.submit_move(mov);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
+use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 pub struct StackVec<const SIZE: usize, T: Sized> {
     len: u8,
     buf: [MaybeUninit<T>; SIZE],
@@ -39,6 +44,10 @@ impl<const SIZE: usize, T: Sized> StackVec<SIZE, T> {
         self.len == 0
     }
 
+    /// Appends the element.
+    ///
+    /// # Panics
+    /// If the vec is already full, see [`Self::try_push`].
     pub fn push(&mut self, elem: T) {
         assert!(self.len < SIZE as u8);
 
@@ -46,10 +55,19 @@ impl<const SIZE: usize, T: Sized> StackVec<SIZE, T> {
         self.len += 1;
     }
 
+    /// Appends the element, or returns it if the vec is already full.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        if self.len as usize == SIZE {
+            return Err(elem);
+        }
+        self.push(elem);
+        Ok(())
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         (self.len > 0).then(|| {
-            let val = std::mem::replace(&mut self.buf[self.len as usize], MaybeUninit::uninit());
             self.len -= 1;
+            let val = std::mem::replace(&mut self.buf[self.len as usize], MaybeUninit::uninit());
             unsafe { val.assume_init() }
         })
     }
@@ -120,3 +138,43 @@ impl<const SIZE: usize, T: Sized> Drop for StackVec<SIZE, T> {
         }
     }
 }
+
+/// Serialized as a sequence of the elements.
+impl<const SIZE: usize, T: Sized + Serialize> Serialize for StackVec<SIZE, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len as usize))?;
+        for elem in self.iter() {
+            seq.serialize_element(elem)?;
+        }
+        seq.end()
+    }
+}
+
+/// Fails if the sequence has more than `SIZE` elements.
+impl<'de, const SIZE: usize, T: Sized + Deserialize<'de>> Deserialize<'de> for StackVec<SIZE, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(StackVecVisitor(PhantomData))
+    }
+}
+
+struct StackVecVisitor<const SIZE: usize, T>(PhantomData<T>);
+
+impl<'de, const SIZE: usize, T: Sized + Deserialize<'de>> Visitor<'de>
+    for StackVecVisitor<SIZE, T>
+{
+    type Value = StackVec<SIZE, T>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a sequence of at most {SIZE} elements")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut vec = StackVec::new();
+        while let Some(elem) = seq.next_element()? {
+            if vec.try_push(elem).is_err() {
+                return Err(de::Error::invalid_length(SIZE + 1, &self));
+            }
+        }
+        Ok(vec)
+    }
+}
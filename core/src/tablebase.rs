@@ -0,0 +1,288 @@
+//! Retrograde analysis of endgames with very few balls.
+//!
+//! With so few balls left the standard win condition can't be reached anymore,
+//! instead the tablebase solves whether the side to move can force pushing off
+//! an opposing ball, and in how many plies.
+
+use std::collections::HashMap;
+
+use crate::{board_cells, Abalone, Color, Move, Pos2};
+
+/// The maximum number of balls on the board a [`Tablebase`] can be built for.
+pub const MAX_TABLEBASE_BALLS: u8 = 3;
+
+/// The result of a position from the perspective of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TbResult {
+    /// The side to move pushes off an opposing ball within `plies`.
+    Win { plies: u16 },
+    /// The opponent pushes off a ball within `plies`.
+    Loss { plies: u16 },
+    /// Neither side can force pushing off a ball.
+    Draw,
+}
+
+/// The results of all positions with a fixed number of black and white balls.
+///
+/// Positions that are rotations or reflections of each other share one entry.
+#[derive(Clone, Debug)]
+pub struct Tablebase {
+    black: u8,
+    white: u8,
+    cells: Vec<Pos2>,
+    symmetries: Vec<Vec<usize>>,
+    index: HashMap<Key, usize>,
+    results: Vec<TbResult>,
+}
+
+/// The balls as bit masks of the indices into [`board_cells`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Key {
+    black: u64,
+    white: u64,
+    black_to_move: bool,
+}
+
+impl Tablebase {
+    /// Solves all positions with `black` and `white` balls, returns [`None`]
+    /// if either color has no balls or there are more than
+    /// [`MAX_TABLEBASE_BALLS`] in total.
+    pub fn build(black: u8, white: u8) -> Option<Tablebase> {
+        if black == 0 || white == 0 || black + white > MAX_TABLEBASE_BALLS {
+            return None;
+        }
+
+        let cells: Vec<Pos2> = board_cells().collect();
+        let symmetries = symmetries(&cells);
+
+        let mut keys = Vec::new();
+        let mut index = HashMap::new();
+        for b in subsets(cells.len(), black) {
+            for w in subsets(cells.len(), white) {
+                if b & w != 0 {
+                    continue;
+                }
+                for black_to_move in [true, false] {
+                    let key = Key {
+                        black: b,
+                        white: w,
+                        black_to_move,
+                    };
+                    let key = key.canonical(&symmetries);
+                    index.entry(key).or_insert_with(|| {
+                        keys.push(key);
+                        keys.len() - 1
+                    });
+                }
+            }
+        }
+
+        // the successors of every position, or `None` if it captures directly
+        let mut base = Abalone::new();
+        for (_, c) in base.iter_mut() {
+            *c = None;
+        }
+        let successors: Vec<Option<Vec<usize>>> = keys
+            .iter()
+            .map(|&key| {
+                let game = key.to_game(&base, &cells);
+                let balls: Vec<Pos2> = key.balls().map(|i| cells[i]).collect();
+                let mut successors = Vec::new();
+                for (_, _, dir, mov) in game.legal_moves_iter() {
+                    if let Move::PushedOff { .. } = mov {
+                        return None;
+                    }
+                    let mut next = game.clone_position();
+                    next.apply_move(mov);
+                    next.turn = next.turn.opposite();
+
+                    // balls either stayed in place or moved in the direction
+                    let moved = balls.iter().map(|&p| p + dir.vec());
+                    let candidates = balls.iter().copied().chain(moved);
+                    let next_key = Key::from_cells(&next, candidates, &cells);
+                    successors.push(index[&next_key.canonical(&symmetries)]);
+                }
+                Some(successors)
+            })
+            .collect();
+
+        let mut results: Vec<Option<TbResult>> = successors
+            .iter()
+            .map(|s| s.is_none().then_some(TbResult::Win { plies: 1 }))
+            .collect();
+        loop {
+            // only use the results of previous iterations, so the distances
+            // are minimal
+            let mut resolved = Vec::new();
+            for (i, successors) in successors.iter().enumerate() {
+                let Some(successors) = successors else {
+                    continue;
+                };
+                if results[i].is_some() || successors.is_empty() {
+                    continue;
+                }
+
+                let fastest_win = (successors.iter())
+                    .filter_map(|&j| match results[j] {
+                        Some(TbResult::Loss { plies }) => Some(plies),
+                        _ => None,
+                    })
+                    .min();
+                if let Some(plies) = fastest_win {
+                    resolved.push((i, TbResult::Win { plies: plies + 1 }));
+                    continue;
+                }
+
+                let slowest_loss = (successors.iter()).try_fold(0, |max, &j| match results[j] {
+                    Some(TbResult::Win { plies }) => Some(max.max(plies)),
+                    _ => None,
+                });
+                if let Some(plies) = slowest_loss {
+                    resolved.push((i, TbResult::Loss { plies: plies + 1 }));
+                }
+            }
+
+            if resolved.is_empty() {
+                break;
+            }
+            for (i, res) in resolved {
+                results[i] = Some(res);
+            }
+        }
+
+        let results = results
+            .into_iter()
+            .map(|r| r.unwrap_or(TbResult::Draw))
+            .collect();
+        Some(Tablebase {
+            black,
+            white,
+            cells,
+            symmetries,
+            index,
+            results,
+        })
+    }
+
+    /// The result of the position, or [`None`] if the number of balls doesn't
+    /// match the tablebase.
+    pub fn probe(&self, game: &Abalone) -> Option<TbResult> {
+        let key = Key::from_cells(game, self.cells.iter().copied(), &self.cells);
+        if key.black.count_ones() != self.black as u32
+            || key.white.count_ones() != self.white as u32
+        {
+            return None;
+        }
+        let key = key.canonical(&self.symmetries);
+        Some(self.results[self.index[&key]])
+    }
+}
+
+impl Key {
+    /// The key of the balls found at the `candidates`, all other cells are
+    /// assumed to be empty.
+    fn from_cells(game: &Abalone, candidates: impl Iterator<Item = Pos2>, cells: &[Pos2]) -> Key {
+        let mut key = Key {
+            black: 0,
+            white: 0,
+            black_to_move: game.turn == Color::Black,
+        };
+        for p in candidates {
+            let Some(&Some(color)) = game.get(p) else {
+                continue;
+            };
+            let i = cell_index(cells, p);
+            match color {
+                Color::Black => key.black |= 1 << i,
+                Color::White => key.white |= 1 << i,
+            }
+        }
+        key
+    }
+
+    /// The smallest key of all symmetric positions.
+    fn canonical(self, symmetries: &[Vec<usize>]) -> Key {
+        let transform = |mask: u64, perm: &[usize]| bits(mask).fold(0, |acc, i| acc | 1 << perm[i]);
+        (symmetries.iter())
+            .map(|perm| Key {
+                black: transform(self.black, perm),
+                white: transform(self.white, perm),
+                black_to_move: self.black_to_move,
+            })
+            .min()
+            .expect("there should be at least the identity")
+    }
+
+    /// The cell indices of all balls.
+    fn balls(self) -> impl Iterator<Item = usize> {
+        bits(self.black | self.white)
+    }
+
+    fn to_game(self, base: &Abalone, cells: &[Pos2]) -> Abalone {
+        let mut game = base.clone_position();
+        for i in self.balls() {
+            game[cells[i]] = match self.black & (1 << i) != 0 {
+                true => Some(Color::Black),
+                false => Some(Color::White),
+            };
+        }
+        game.turn = match self.black_to_move {
+            true => Color::Black,
+            false => Color::White,
+        };
+        game
+    }
+}
+
+/// The 6 rotations around the center, with and without reflection, as
+/// permutations of the cell indices, see [`Abalone::canonical_hash`].
+fn symmetries(cells: &[Pos2]) -> Vec<Vec<usize>> {
+    let mut symmetries = Vec::new();
+    for reflected in [false, true] {
+        for rotations in 0..6 {
+            let perm = (cells.iter())
+                .map(|&p| {
+                    let p = match reflected {
+                        true => Pos2 { x: p.y, y: p.x },
+                        false => p,
+                    };
+                    cell_index(cells, p.rotate(rotations))
+                })
+                .collect();
+            symmetries.push(perm);
+        }
+    }
+    symmetries
+}
+
+fn cell_index(cells: &[Pos2], pos: Pos2) -> usize {
+    cells
+        .binary_search_by_key(&(pos.y, pos.x), |c| (c.y, c.x))
+        .expect("position should be in bounds")
+}
+
+/// The indices of all set bits.
+fn bits(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let i = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(i)
+    })
+}
+
+/// All bit masks of `k` out of the lowest `n` bits.
+fn subsets(n: usize, k: u8) -> Vec<u64> {
+    if k == 0 {
+        return vec![0];
+    }
+    let mut masks = Vec::new();
+    for i in (k as usize - 1)..n {
+        for rest in subsets(i, k - 1) {
+            masks.push(rest | 1 << i);
+        }
+    }
+    masks
+}
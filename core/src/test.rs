@@ -1,13 +1,133 @@
-use crate::{Abalone, Dir, Error, Move, MoveError, Pos2, SelectionError, Vec2};
+use crate::ai::{self, SearchConfig};
+use crate::bitboard::BitboardError;
+use crate::fen::FenError;
+use crate::notation::Orientation;
+use crate::script::{load_collection, CollectionError, ScriptError};
+use crate::stackvec::StackVec;
+use crate::tablebase::{Tablebase, TbResult};
+use crate::transcript::SavedGame;
+use crate::{
+    extend_selection, Abalone, ApplyError, Color, Dir, DrawReason, Error, ExtendError, GameEvent,
+    GameStatus, InvariantError, Move, MoveError, Outcome, Pos2, RunEnd, SelectionError, SetupError,
+    Standing, StartPosition, Vec2,
+};
+
+impl Abalone {
+    /// Checks and submits a move, panicking if it isn't legal.
+    fn then_move(mut self, first: impl Into<Pos2>, last: impl Into<Pos2>, dir: Dir) -> Self {
+        let mov = self.check_move([first.into(), last.into()], dir);
+        let mov = mov.unwrap_or_else(|e| panic!("{e}\n{self}"));
+        self.submit_move(mov);
+        self
+    }
+}
+
+/// A position with only the `white` and `black` balls, white is on turn.
+fn board(white: &[(i8, i8)], black: &[(i8, i8)]) -> Abalone {
+    let mut game = Abalone::new();
+    for (_, c) in game.iter_mut() {
+        *c = None;
+    }
+    for &p in white {
+        game[p] = Some(Color::White);
+    }
+    for &p in black {
+        game[p] = Some(Color::Black);
+    }
+    game
+}
+
+/// Like [`board`], but with reserves of 8 balls per color at the bottom of the
+/// board, so the balls missing from the board don't already decide the game.
+fn board_with_reserves(white: &[(i8, i8)], black: &[(i8, i8)]) -> Abalone {
+    let mut game = board(white, black);
+    for p in [
+        (3, 7),
+        (4, 7),
+        (5, 7),
+        (6, 7),
+        (4, 8),
+        (5, 8),
+        (6, 8),
+        (7, 8),
+    ] {
+        game[p] = Some(Color::White);
+    }
+    for p in [
+        (1, 5),
+        (2, 6),
+        (3, 6),
+        (4, 6),
+        (5, 6),
+        (6, 6),
+        (7, 6),
+        (8, 6),
+    ] {
+        game[p] = Some(Color::Black);
+    }
+    game
+}
+
+/// A tiny deterministic random number generator for simulations.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        crate::splitmix64(&mut self.0)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Plays a game from the start position until there is a winner, there are no
+/// legal moves left or `max_plies` moves have been played. Every move chosen by
+/// the policy has to be legal.
+fn simulate(
+    seed: u64,
+    max_plies: usize,
+    mut policy: impl FnMut(&Abalone, &mut Rng) -> Move,
+) -> Abalone {
+    let mut rng = Rng(seed);
+    let mut game = Abalone::new();
+    while game.move_idx < max_plies && game.winner().is_none() && game.has_legal_move() {
+        let mov = policy(&game, &mut rng);
+        assert_eq!(game.validate_move(mov), Ok(()), "{mov:?}\n{game}");
+        game.submit_move(mov);
+    }
+    game
+}
+
+/// Pushes off a random opposing ball if possible, otherwise plays a random move.
+fn greedy_policy(game: &Abalone, rng: &mut Rng) -> Move {
+    let moves = game.legal_moves();
+    let pushing_off: Vec<_> = moves
+        .iter()
+        .filter(|(_, _, _, m)| matches!(m, Move::PushedOff { .. }))
+        .collect();
+    if !pushing_off.is_empty() {
+        return pushing_off[rng.below(pushing_off.len())].3;
+    }
+    moves[rng.below(moves.len())].3
+}
+
+/// Plays a random move.
+fn random_policy(game: &Abalone, rng: &mut Rng) -> Move {
+    let moves = game.legal_moves();
+    moves[rng.below(moves.len())].3
+}
 
 struct CheckState {
     game: Abalone,
 }
 
 fn start() -> CheckState {
-    CheckState {
-        game: Abalone::new(),
-    }
+    start_from(Abalone::new())
+}
+
+fn start_from(game: Abalone) -> CheckState {
+    CheckState { game }
 }
 
 impl CheckState {
@@ -49,6 +169,11 @@ impl CheckState {
         assert_eq!(res.err(), None, "\n{}", self.game);
         self
     }
+
+    fn check_standing(self, expected: Standing) -> Self {
+        assert_eq!(self.game.standing(), expected, "\n{}", self.game);
+        self
+    }
 }
 
 #[test]
@@ -201,3 +326,1885 @@ fn parallel() {
     check_not((1, 2), (2, 1));
     check_not((3, 2), (2, 1));
 }
+
+#[test]
+fn edges() {
+    let mut num_edges = 0;
+    for y in 0..9 {
+        for x in 0..9 {
+            let pos = Pos2 { x, y };
+            if !crate::is_in_bounds(pos) {
+                assert!(!crate::is_edge(pos), "{pos} is not on the board");
+                continue;
+            }
+
+            let on_ring = x == 0 || y == 0 || x == 8 || y == 8 || x - y == 4 || y - x == 4;
+            assert_eq!(crate::is_edge(pos), on_ring, "{pos}");
+            if on_ring {
+                num_edges += 1;
+            }
+        }
+    }
+
+    assert_eq!(num_edges, 24);
+    assert_eq!(crate::edge_cells().count(), 24);
+    assert!(crate::edge_cells().all(crate::is_edge));
+}
+
+#[test]
+fn start_positions() {
+    for start in [
+        StartPosition::Standard,
+        StartPosition::BelgianDaisy,
+        StartPosition::GermanDaisy,
+        StartPosition::Snakes,
+    ] {
+        let game = Abalone::with_start_position(start);
+        let count = |color| game.iter().filter(|&(_, _, c)| c == Some(color)).count();
+        assert_eq!(count(Color::Black), 14, "{start:?}\n{game}");
+        assert_eq!(count(Color::White), 14, "{start:?}\n{game}");
+    }
+}
+
+#[test]
+fn is_starting_position() {
+    for start in StartPosition::ALL {
+        let game = Abalone::with_start_position(start);
+        assert_eq!(game.is_starting_position(), Some(start));
+    }
+
+    let game = Abalone::new().then_move((4, 6), (4, 6), Dir::NegZ);
+    assert_eq!(game.is_starting_position(), None);
+
+    // balls moved back to the start, ignoring the move history
+    let game = game.then_move((2, 2), (2, 2), Dir::PosY);
+    let game = game.then_move((3, 5), (3, 5), Dir::PosZ);
+    let game = game.then_move((2, 3), (2, 3), Dir::NegY);
+    assert_eq!(game.is_starting_position(), Some(StartPosition::Standard));
+}
+
+#[test]
+fn transcript_start_position() {
+    let mut game = Abalone::with_start_position(StartPosition::BelgianDaisy);
+    let mut saved = SavedGame::new(StartPosition::BelgianDaisy);
+    for (pos, dir) in [((4, 6), Dir::NegY), ((6, 6), Dir::NegY)] {
+        let mov = game.check_move([pos.into(); 2], dir).unwrap();
+        game.submit_move(mov);
+        saved.moves.push(mov);
+    }
+    assert_eq!(Abalone::from_transcript(&saved), Ok(game));
+
+    saved.start = StartPosition::Standard;
+    let expected = ApplyError::Illegal(Error::Selection(SelectionError::WrongTurn((6, 6).into())));
+    assert_eq!(Abalone::from_transcript(&saved), Err((1, expected)));
+}
+
+#[test]
+fn legal_moves() {
+    fn check(game: &Abalone) {
+        let apply = |mov| {
+            let mut g = game.clone();
+            g.submit_move(mov);
+            g.balls
+        };
+
+        // all distinct resulting boards of any selection and direction, that
+        // move balls of the current player
+        let mut expected = Vec::new();
+        for first in (0..81).map(|i| Pos2::from((i % 9, i / 9))) {
+            for last in (0..81).map(|i| Pos2::from((i % 9, i / 9))) {
+                if !crate::is_in_bounds(first) || !crate::is_in_bounds(last) {
+                    continue;
+                }
+                for dir in crate::DIRS {
+                    if let Ok(mov) = game.check_move([first, last], dir) {
+                        let (Move::PushedOff { first, .. }
+                        | Move::PushedAway { first, .. }
+                        | Move::Moved { first, .. }) = mov
+                        else {
+                            unreachable!("check_move never passes");
+                        };
+                        if game[first] != Some(game.turn) {
+                            continue;
+                        }
+
+                        let balls = apply(mov);
+                        if !expected.contains(&balls) {
+                            expected.push(balls);
+                        }
+                    }
+                }
+            }
+        }
+
+        let moves = game.legal_moves();
+        let iterated: Vec<_> = game.legal_moves_iter().collect();
+        assert_eq!(moves, iterated);
+        assert_eq!(moves.len(), expected.len(), "\n{game}");
+        for &(first, last, dir, mov) in moves.iter() {
+            assert_eq!(game.check_move([first, last], dir), Ok(mov));
+            assert!(expected.contains(&apply(mov)), "{mov:?}");
+        }
+    }
+
+    let mut game = Abalone::new();
+    check(&game);
+
+    for (first, last, dir) in [
+        ((8, 8), (6, 6), Dir::NegZ),
+        ((0, 0), (2, 2), Dir::PosZ),
+        ((5, 5), (5, 5), Dir::NegZ),
+    ] {
+        let mov = game.check_move([first.into(), last.into()], dir).unwrap();
+        game.submit_move(mov);
+    }
+    assert!(game
+        .legal_moves_iter()
+        .any(|(_, _, _, m)| matches!(m, Move::PushedAway { .. })));
+    check(&game);
+}
+
+#[test]
+fn apply_if_unchanged() {
+    let mut game = Abalone::new();
+    let hash = game.zobrist_hash();
+    let selection = [(4, 6).into(), (4, 6).into()];
+    let mov = game.apply_if_unchanged(hash, selection, Dir::NegY).unwrap();
+    assert_eq!(game.moves, [mov]);
+
+    // a stale move proposed for the previous position
+    let before = game.clone();
+    let res = game.apply_if_unchanged(hash, [(2, 2).into(); 2], Dir::PosY);
+    let expected = ApplyError::PositionChanged {
+        expected: hash,
+        actual: game.zobrist_hash(),
+    };
+    assert_eq!(res, Err(expected));
+    assert_eq!(game, before);
+}
+
+#[test]
+fn iter_mut() {
+    let mut game = Abalone::new();
+    assert_eq!(game.iter_mut().count(), 61);
+    assert!(game.iter_mut().all(|(p, _)| crate::is_in_bounds(p)));
+
+    for (pos, val) in game.iter_mut() {
+        *val = (pos == Pos2::from((4, 4))).then_some(Color::Black);
+    }
+    assert_eq!(game.get((4, 4)), Some(&Some(Color::Black)));
+    assert_eq!(game.get((0, 0)), Some(&None));
+    assert_eq!(game.iter().filter(|(_, _, c)| c.is_some()).count(), 1);
+}
+
+#[test]
+fn standing() {
+    let mut game = Abalone::new();
+    assert_eq!(game.scores(), (0, 0));
+    assert_eq!(game.standing(), Standing::Ongoing);
+
+    for x in 4..9 {
+        game[(x, 8)] = None;
+    }
+    assert_eq!(game.scores(), (5, 0));
+    assert_eq!(game.winner(), None);
+
+    game[(3, 7)] = None;
+    assert_eq!(game.winner(), Some(Color::Black));
+    assert_eq!(game.standing(), Standing::Win(Color::Black));
+}
+
+#[test]
+fn standing_draw_by_limit() {
+    let mut game = Abalone::new();
+    game.rules.move_limit = Some(2);
+    game[(8, 8)] = None;
+
+    start_from(game)
+        .assert_move((4, 6), (4, 6), Dir::NegY)
+        .check_standing(Standing::Ongoing)
+        .assert_move((2, 2), (2, 2), Dir::PosY)
+        .check_standing(Standing::DrawByLimit { black: 1, white: 0 });
+}
+
+#[test]
+fn max_history() {
+    let mut game = Abalone::new();
+    game.rules.max_history = Some(2);
+    let game = game
+        .then_move((4, 6), (6, 6), Dir::NegY)
+        .then_move((2, 2), (4, 2), Dir::PosY)
+        .then_move((4, 5), (6, 5), Dir::NegY);
+    assert_eq!(game.moves.len(), 2);
+    assert_eq!(game.ply(), 3);
+
+    let mut undone = game.clone();
+    undone.undo_move();
+    undone.undo_move();
+    assert!(!undone.can_undo());
+    assert_eq!(undone.ply(), 1);
+    assert_eq!(
+        undone.balls,
+        Abalone::new().then_move((4, 6), (6, 6), Dir::NegY).balls
+    );
+
+    // redoing keeps the capped history intact
+    undone.redo_move();
+    undone.redo_move();
+    assert_eq!(undone, game);
+}
+
+#[test]
+fn pass_turn() {
+    let mut game = Abalone::new().then_move((4, 6), (6, 6), Dir::NegY);
+    let before = game.clone();
+
+    game.pass_turn();
+    assert_eq!(game.turn, Color::White);
+    assert_eq!(game.balls, before.balls);
+    assert_eq!(game.moves.last(), Some(&Move::Pass));
+
+    game.undo_move();
+    assert_eq!(game.turn, before.turn);
+    assert_eq!(game.balls, before.balls);
+
+    // passes are only legal without any legal move
+    game.redo_move();
+    assert!(game.must_move());
+    assert_eq!(game.check_pass(), Err(Error::PassNotAllowed));
+    let mut replayed = Abalone::new();
+    let err = ApplyError::Illegal(Error::PassNotAllowed);
+    assert_eq!(replayed.apply_many(&game.moves), Err((1, err)));
+    assert_eq!(replayed.moves, game.moves[..1]);
+}
+
+#[test]
+fn report() {
+    let white = [
+        (0, 0),
+        (4, 0),
+        (4, 1),
+        (4, 2),
+        (4, 8),
+        (5, 8),
+        (6, 8),
+        (7, 8),
+        (8, 8),
+    ];
+    let black = [
+        (1, 1),
+        (2, 2),
+        (4, 3),
+        (4, 4),
+        (7, 4),
+        (8, 4),
+        (8, 5),
+        (8, 6),
+        (8, 7),
+    ];
+    let game = board(&white, &black);
+
+    let game = game
+        .then_move((4, 0), (4, 0), Dir::PosY)
+        .then_move((2, 2), (2, 2), Dir::NegZ);
+    let report = game.report();
+    assert_eq!(report.plies, 2);
+    assert_eq!(report.black_captures, 6);
+    assert_eq!(report.white_captures, 5);
+    assert_eq!(report.winner, Some(Color::Black));
+    assert_eq!(report.longest_push, 2);
+    assert_eq!(report.final_board, game.snapshot());
+}
+
+#[test]
+fn successors() {
+    let opening = Abalone::new();
+    let successors = opening.successors();
+    assert_eq!(successors.len(), 44);
+    for s in successors.iter() {
+        assert_eq!(s.turn, Color::Black);
+        assert!(s.moves.is_empty());
+    }
+
+    let mov = opening.check_move([(4, 6).into(); 2], Dir::NegY).unwrap();
+    let mut expected = opening.clone_position();
+    expected.submit_move(mov);
+    assert!(successors
+        .iter()
+        .any(|s| s.balls == expected.balls && s.turn == expected.turn));
+}
+
+#[test]
+fn tablebase() {
+    assert!(Tablebase::build(0, 2).is_none());
+    assert!(Tablebase::build(2, 2).is_none());
+
+    let tb = Tablebase::build(1, 2).unwrap();
+    let mut game = board(&[(1, 1), (2, 2)], &[(0, 0)]);
+
+    // white pushes the black ball off the corner
+    assert_eq!(tb.probe(&game), Some(TbResult::Win { plies: 1 }));
+
+    // the same position in the opposite corner
+    let rotated = board(&[(7, 7), (6, 6)], &[(8, 8)]);
+    assert_eq!(tb.probe(&rotated), Some(TbResult::Win { plies: 1 }));
+
+    // black escapes along the edge
+    game.turn = Color::Black;
+    assert_eq!(tb.probe(&game), Some(TbResult::Draw));
+
+    game[(3, 3)] = Some(Color::White);
+    assert_eq!(tb.probe(&game), None);
+}
+
+#[test]
+fn swap_colors() {
+    let game = simulate(5, 60, greedy_policy);
+
+    let mut swapped = game.clone();
+    swapped.swap_colors();
+    assert_eq!(swapped.turn, game.turn.opposite());
+    let (black, white) = game.scores();
+    assert_eq!(swapped.scores(), (white, black));
+    assert_eq!(
+        swapped.material_balance(Color::White),
+        game.material_balance(Color::Black)
+    );
+
+    swapped.swap_colors();
+    assert_eq!(swapped, game);
+}
+
+#[test]
+fn recent_notation() {
+    let mut game = Abalone::new()
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (4, 2), Dir::PosY)
+        .then_move((4, 8), (4, 8), Dir::NegY);
+    assert_eq!(game.recent_notation(2), ["C3C5D3", "I5H5"]);
+    assert_eq!(game.recent_notation(10), ["G5F4", "C3C5D3", "I5H5"]);
+
+    game.undo_move();
+    assert_eq!(game.recent_notation(10), ["G5F4", "C3C5D3"]);
+    assert!(Abalone::new().recent_notation(3).is_empty());
+}
+
+#[test]
+fn check_single() {
+    let game = Abalone::new();
+    for pos in [(4, 6), (2, 2), (0, 0)] {
+        for dir in crate::DIRS {
+            let single = game.check_single(pos.into(), dir);
+            assert_eq!(single, game.check_move([pos.into(); 2], dir));
+        }
+    }
+
+    let mov = game.check_single((4, 6).into(), Dir::NegY);
+    let expected = Move::Moved {
+        dir: Dir::NegY,
+        first: (4, 6).into(),
+        last: (4, 6).into(),
+    };
+    assert_eq!(mov, Ok(expected));
+}
+
+#[test]
+fn fen() {
+    let opening = Abalone::new();
+    let fen = opening.to_fen();
+    assert_eq!(fen, "bbbbb/bbbbbb/2bbb2/8/9/8/2www2/wwwwww/wwwww w");
+    assert_eq!(Abalone::from_fen(&fen), Ok(opening));
+
+    let game = simulate(3, 31, random_policy);
+    let parsed = Abalone::from_fen(&game.to_fen()).unwrap();
+    assert_eq!(parsed.balls, game.balls);
+    assert_eq!(parsed.turn, Color::Black);
+    assert!(parsed.moves.is_empty());
+
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb2/8/9/8/2www2/wwwwww/wwwww");
+    assert_eq!(res, Err(FenError::MissingTurn));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb2/8/9/8/2www2/wwwwww w");
+    assert_eq!(res, Err(FenError::RowCount(8)));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb3/8/9/8/2www2/wwwwww/wwwww w");
+    assert_eq!(res, Err(FenError::RowLength { row: 2, len: 8 }));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb1/8/9/8/2www2/wwwwww/wwwww w");
+    assert_eq!(res, Err(FenError::RowLength { row: 2, len: 6 }));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bxb2/8/9/8/2www2/wwwwww/wwwww w");
+    assert_eq!(res, Err(FenError::InvalidChar('x')));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb2/8/b8/8/2www2/wwwwww/wwwww w");
+    assert_eq!(res, Err(FenError::TooManyBalls(Color::Black)));
+    let res = Abalone::from_fen("bbbbb/bbbbbb/2bbb2/8/9/w7/2www2/wwwwww/wwwww b");
+    assert_eq!(res, Err(FenError::TooManyBalls(Color::White)));
+}
+
+#[test]
+fn material_balance() {
+    let mut game = Abalone::new();
+    assert_eq!(game.material_balance(Color::Black), 0);
+    assert_eq!(game.material_balance(Color::White), 0);
+
+    game[(8, 8)] = None;
+    game[(7, 8)] = None;
+    game[(0, 0)] = None;
+    assert_eq!(game.material_balance(Color::Black), 1);
+    assert_eq!(game.material_balance(Color::White), -1);
+}
+
+#[test]
+fn simulation() {
+    let policy = |game: &Abalone, rng: &mut Rng| match game.turn {
+        Color::Black => greedy_policy(game, rng),
+        Color::White => random_policy(game, rng),
+    };
+    let seed = 0xABA10E;
+    let game = simulate(seed, 2000, policy);
+    assert_eq!(game.winner(), Some(Color::Black), "\n{game}");
+    assert_eq!(game, simulate(seed, 2000, policy));
+}
+
+#[test]
+fn opposing_run() {
+    let mut game = Abalone::new();
+    game[(4, 5)] = Some(Color::Black);
+
+    // (4, 4) is empty
+    let run = game.opposing_run((4, 5).into(), Dir::NegY, Color::White);
+    assert_eq!(run, (1, RunEnd::Empty));
+    // (4, 6) is white
+    let run = game.opposing_run((4, 5).into(), Dir::PosY, Color::White);
+    assert_eq!(run, (1, RunEnd::Own));
+    let run = game.opposing_run((0, 1).into(), Dir::NegY, Color::White);
+    assert_eq!(run, (2, RunEnd::Edge));
+    let run = game.opposing_run((4, 4).into(), Dir::PosY, Color::White);
+    assert_eq!(run, (0, RunEnd::Empty));
+}
+
+#[test]
+fn snapshots() {
+    let start = Abalone::new();
+    let mut game = start
+        .clone()
+        .then_move((4, 6), (6, 6), Dir::NegY)
+        .then_move((2, 2), (4, 2), Dir::PosY)
+        .then_move((4, 5), (6, 5), Dir::NegY);
+    game.undo_move();
+
+    let snapshots = game.snapshots();
+    assert_eq!(snapshots.len(), game.move_idx + 1);
+    assert_eq!(snapshots[0], start.snapshot());
+    assert_eq!(snapshots.last(), Some(&game.snapshot()));
+    assert_ne!(snapshots[1], snapshots[0]);
+
+    assert_eq!(game.replay().count(), snapshots.len());
+    for (i, (ply, position)) in game.replay().enumerate() {
+        assert_eq!(ply, i);
+        assert_eq!(position, game.board_at(i));
+    }
+}
+
+#[test]
+fn inevitable_capture() {
+    let game = Abalone::new();
+    assert!(game.threatened(Color::White).is_empty());
+    assert!(!game.inevitable_capture());
+
+    let white = [
+        (0, 0),
+        (4, 8),
+        (5, 8),
+        (6, 8),
+        (7, 8),
+        (8, 8),
+        (3, 7),
+        (4, 7),
+        (5, 7),
+        (6, 7),
+    ];
+    let black = [
+        (1, 1),
+        (2, 2),
+        (2, 1),
+        (8, 4),
+        (7, 4),
+        (6, 4),
+        (8, 5),
+        (7, 5),
+        (6, 5),
+    ];
+    let mut game = board(&white, &black);
+
+    // (0, 0) can escape to (1, 0)
+    assert_eq!(game.winner(), None);
+    assert_eq!(game.threatened(Color::White), [Pos2::from((0, 0))]);
+    assert!(!game.inevitable_capture(), "\n{game}");
+
+    // now (1, 0) is threatened as well
+    game[(1, 2)] = Some(Color::Black);
+    assert!(game.inevitable_capture(), "\n{game}");
+}
+
+#[test]
+fn apply_many() {
+    let played = Abalone::new()
+        .then_move((4, 6), (6, 6), Dir::NegY)
+        .then_move((2, 2), (4, 2), Dir::PosY)
+        .then_move((4, 5), (6, 5), Dir::NegY);
+
+    let mut game = Abalone::new();
+    assert_eq!(game.apply_many(&played.moves), Ok(()));
+    assert_eq!(game, played);
+
+    // the balls of the last move were already moved
+    let mut moves = played.moves.clone();
+    moves.push(moves[2]);
+    let mut game = Abalone::new();
+    let res = game.apply_many(&moves);
+    assert!(matches!(res, Err((3, ApplyError::Illegal(_)))), "{res:?}");
+    assert_eq!(game, played);
+}
+
+#[test]
+fn pos_vec_sub() {
+    let pos = Pos2::from((4, 4));
+
+    let shifted: Pos2 = pos - Vec2::new(1, 2);
+    assert_eq!(shifted, Pos2::from((3, 2)));
+
+    let displacement: Vec2 = pos - Pos2::from((1, 2));
+    assert_eq!(displacement, Vec2::new(3, 2));
+
+    assert_eq!(shifted + displacement, Pos2::from((6, 4)));
+}
+
+#[test]
+fn run_script() {
+    let script = "
+        # opening
+        W G5-F4
+        B C3-D3
+
+        undo  # take it back
+        B C3C5D3
+        W G6G7F6
+    ";
+    let mut game = Abalone::new();
+    assert_eq!(game.run_script(script), Ok(()));
+
+    let expected = Abalone::new()
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (4, 2), Dir::PosY)
+        .then_move((5, 6), (6, 6), Dir::NegY);
+    assert_eq!(game.balls, expected.balls);
+    assert_eq!(game.turn, expected.turn);
+
+    let mut game = Abalone::new();
+    let res = game.run_script("W G5-F4\nW G6-F5");
+    assert_eq!(
+        res,
+        Err(ScriptError::WrongColor {
+            line: 2,
+            expected: Color::Black
+        })
+    );
+    assert_eq!(game.moves.len(), 1);
+
+    let res = Abalone::new().run_script("W G5-F4\nredo");
+    assert_eq!(res, Err(ScriptError::NothingToRedo { line: 2 }));
+    let res = Abalone::new().run_script("W G5-E3");
+    assert_eq!(res, Err(ScriptError::Syntax { line: 1 }));
+}
+
+#[test]
+fn legal_move_count() {
+    let opening = Abalone::new();
+    assert_eq!(opening.legal_move_count(), 44);
+
+    let midgame = simulate(7, 40, random_policy);
+    let policy = |game: &Abalone, rng: &mut Rng| match game.turn {
+        Color::Black => greedy_policy(game, rng),
+        Color::White => random_policy(game, rng),
+    };
+    let endgame = simulate(0xABA10E, 2000, policy);
+    assert!(endgame.winner().is_some());
+    let mut near_endgame = endgame.clone();
+    near_endgame.undo_move();
+
+    for game in [opening, midgame, near_endgame, endgame] {
+        assert_eq!(
+            game.legal_move_count(),
+            game.legal_moves().len(),
+            "\n{game}"
+        );
+    }
+}
+
+#[test]
+fn out_of_bounds() {
+    let game = Abalone::new();
+
+    // inside the 9x9 array, but not on the hex
+    let res = game.check_move([(8, 0).into(); 2], Dir::NegX);
+    let expected = SelectionError::OutOfBounds((8, 0).into());
+    assert_eq!(res, Err(expected.clone().into()));
+    assert_eq!(game.check_selection([(8, 0).into(); 2]), Err(expected));
+
+    let res = game.check_move([(4, 6).into(), (4, 9).into()], Dir::NegX);
+    let expected = SelectionError::OutOfBounds((4, 9).into());
+    assert_eq!(res, Err(expected.into()));
+}
+
+#[test]
+fn color_opposite() {
+    for color in [Color::Black, Color::White] {
+        assert_ne!(color.opposite(), color);
+        assert_eq!(color.opposite().opposite(), color);
+    }
+}
+
+#[test]
+fn must_move() {
+    let game = Abalone::new();
+    assert!(game.has_legal_move());
+    assert!(game.must_move());
+
+    // a lone ball surrounded by opposing ones can't move
+    let game = board(&[(0, 0)], &[(1, 0), (0, 1), (1, 1)]);
+    assert!(!game.has_legal_move());
+    assert!(!game.must_move());
+}
+
+#[test]
+fn push_preview() {
+    let mut game = board(&[(1, 1), (2, 2), (4, 4)], &[(0, 0), (3, 3)]);
+
+    // 2 white balls push off the black one, sideward the way is free
+    let dirs = game.legal_dirs([(1, 1).into(), (2, 2).into()]);
+    let counts: Vec<_> = dirs
+        .iter()
+        .map(|&(dir, mov)| (dir, game.pushed_count(mov)))
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            (Dir::PosX, 0),
+            (Dir::PosY, 0),
+            (Dir::NegX, 0),
+            (Dir::NegY, 0),
+            (Dir::NegZ, 1),
+        ]
+    );
+    assert!(matches!(dirs[4].1, Move::PushedOff { .. }));
+
+    // blocked by the white ball behind the black one
+    let mov = game.check_move([(1, 1).into(); 2], Dir::PosZ);
+    assert!(mov.is_err());
+
+    // 2 white balls push the black one away
+    game[(4, 4)] = None;
+    let mov = game.check_move([(1, 1).into(); 2], Dir::PosZ).unwrap();
+    assert!(matches!(mov, Move::PushedAway { .. }));
+    assert_eq!(game.pushed_count(mov), 1);
+
+    // nothing selected
+    assert_eq!(game.legal_dirs([(5, 5).into(); 2]), []);
+}
+
+#[test]
+fn cells_center_out() {
+    let game = Abalone::new();
+    let center = Pos2::from((4, 4));
+    let cells: Vec<Pos2> = game.cells_center_out().collect();
+    assert_eq!(cells.len(), 61);
+    assert_eq!(cells[0], center);
+
+    let dists: Vec<i8> = cells.iter().map(|&p| (p - center).mag()).collect();
+    assert!(dists.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(dists.last(), Some(&4));
+}
+
+#[test]
+fn material_effect() {
+    let game = board(&[(1, 1), (2, 2)], &[(0, 0), (3, 3)]);
+
+    let moved = game.check_move([(1, 1).into(), (2, 2).into()], Dir::PosX);
+    assert!(matches!(moved, Ok(Move::Moved { .. })));
+    assert_eq!(game.material_effect(moved.unwrap()), 0);
+
+    let pushed_away = game.check_move([(1, 1).into(); 2], Dir::PosZ);
+    assert!(matches!(pushed_away, Ok(Move::PushedAway { .. })));
+    assert_eq!(game.material_effect(pushed_away.unwrap()), 0);
+
+    let pushed_off = game.check_move([(2, 2).into(); 2], Dir::NegZ);
+    assert!(matches!(pushed_off, Ok(Move::PushedOff { .. })));
+    assert_eq!(game.material_effect(pushed_off.unwrap()), 1);
+}
+
+#[test]
+fn equals_ignoring_turn() {
+    let game = Abalone::new();
+    let mut other = game.clone();
+    other.turn = other.turn.opposite();
+    assert!(game.equals_ignoring_turn(&other));
+    assert_ne!(game, other);
+
+    other[(0, 0)] = None;
+    assert!(!game.equals_ignoring_turn(&other));
+}
+
+#[test]
+fn collection() {
+    let collection = "
+        W G5-F4
+        B C3-D3
+        ---
+        # second game
+        W G6G7F6
+    ";
+    let games = load_collection(collection).unwrap();
+    assert_eq!(games.len(), 2);
+    let expected = Abalone::new()
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (2, 2), Dir::PosY);
+    assert_eq!(games[0], expected);
+    let expected = Abalone::new().then_move((5, 6), (6, 6), Dir::NegY);
+    assert_eq!(games[1], expected);
+
+    let res = load_collection("W G5-F4\n---\nW G5-F4\nW G6-F5\n---\n");
+    let error = ScriptError::WrongColor {
+        line: 2,
+        expected: Color::Black,
+    };
+    assert_eq!(res, Err(CollectionError { game: 1, error }));
+}
+
+#[test]
+fn rotate_60() {
+    let cycle = [
+        Dir::PosX,
+        Dir::PosZ,
+        Dir::PosY,
+        Dir::NegX,
+        Dir::NegZ,
+        Dir::NegY,
+    ];
+    let mut vec = crate::UNIT_X;
+    for dir in cycle {
+        assert_eq!(vec, dir.vec());
+        vec = vec.rotate_60();
+    }
+    assert_eq!(vec, crate::UNIT_X);
+
+    assert_eq!(Vec2::new(2, -1).rotate_60(), Vec2::new(3, 2));
+}
+
+#[test]
+fn explain() {
+    let game = board(&[(1, 1), (2, 2), (3, 3)], &[(4, 4), (5, 5), (6, 6)]);
+    let err = game
+        .check_move([(1, 1).into(), (3, 3).into()], Dir::PosZ)
+        .unwrap_err();
+    assert_eq!(
+        err.explain(),
+        "You can't push 3 opposing balls with 3 or fewer of yours, you need more balls in a row than your opponent."
+    );
+
+    let game = board(&[(2, 2), (3, 3), (5, 5)], &[(4, 4)]);
+    let err = game
+        .check_move([(2, 2).into(), (3, 3).into()], Dir::PosZ)
+        .unwrap_err();
+    assert_eq!(err, Error::Move(MoveError::BlockedByOwn((5, 5).into())));
+    assert_eq!(
+        err.explain(),
+        "Your own ball at F6 is in the way, opposing balls can only be pushed into free cells or off the board."
+    );
+}
+
+#[test]
+fn spectator_view() {
+    let mut game = Abalone::new()
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (2, 2), Dir::PosY);
+    game.undo_move();
+
+    let view = game.spectator_view();
+    assert_eq!(view.balls, game.balls);
+    assert_eq!(view.scores, (0, 0));
+    assert_eq!(view.turn, Color::Black);
+    assert_eq!(view.moves, ["G5F4"]);
+    assert_eq!(view.winner, None);
+}
+
+#[test]
+fn position_key() {
+    let a = Abalone::new()
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (2, 2), Dir::PosY)
+        .then_move((5, 6), (5, 6), Dir::NegZ)
+        .then_move((3, 2), (3, 2), Dir::PosY);
+    let b = Abalone::new()
+        .then_move((5, 6), (5, 6), Dir::NegZ)
+        .then_move((3, 2), (3, 2), Dir::PosY)
+        .then_move((4, 6), (4, 6), Dir::NegZ)
+        .then_move((2, 2), (2, 2), Dir::PosY);
+    assert_ne!(a.moves, b.moves);
+    assert_eq!(a.position_key(), b.position_key());
+    assert_ne!(a.position_key(), Abalone::new().position_key());
+}
+
+#[test]
+fn movable_balls() {
+    let opening = Abalone::new();
+    // balls in the back can move the line in front of them
+    assert_eq!(opening.movable_balls(Color::White).count_ones(), 14);
+    assert_eq!(opening.movable_balls(Color::White), opening.bitboards().1);
+
+    let midgame = simulate(3, 30, random_policy);
+    for game in [opening, midgame] {
+        for color in [Color::Black, Color::White] {
+            let movable = (game.iter())
+                .filter(|&(x, y, c)| c == Some(color) && game.can_ball_move((x, y).into()))
+                .count();
+            assert_eq!(game.movable_balls(color).count_ones() as usize, movable);
+
+            // the bits match the ones of the bitboards
+            let (black, white) = game.bitboards();
+            let balls = if color == Color::Black { black } else { white };
+            assert_eq!(game.movable_balls(color) & !balls, 0);
+        }
+    }
+}
+
+#[test]
+fn sextants() {
+    let sextants = [
+        (0, Dir::PosX),
+        (1, Dir::PosZ),
+        (2, Dir::PosY),
+        (3, Dir::NegX),
+        (4, Dir::NegZ),
+        (5, Dir::NegY),
+    ];
+    for (idx, dir) in sextants {
+        assert_eq!(Dir::from_sextant(idx), dir);
+        assert_eq!(Dir::from_sextant(idx + 6), dir);
+        assert_eq!(dir.sextant(), idx);
+        assert_eq!(Dir::from_sextant(idx + 1).vec(), dir.vec().rotate_60());
+    }
+}
+
+#[test]
+fn evaluate_line() {
+    let game = board_with_reserves(&[(1, 1), (2, 2), (8, 8)], &[(0, 0), (4, 4), (4, 5)]);
+    let before = game.clone();
+
+    // white pushes off a black ball, black moves away
+    let line = [
+        ((2, 2).into(), (2, 2).into(), Dir::NegZ),
+        ((4, 4).into(), (4, 5).into(), Dir::PosX),
+    ];
+    let end = (game.clone())
+        .then_move((2, 2), (2, 2), Dir::NegZ)
+        .then_move((4, 4), (4, 5), Dir::PosX);
+    let score = ai::evaluate(&end, Color::White);
+    assert_eq!(game.evaluate_line(&line, Color::White), Ok(score));
+    assert_eq!(game.evaluate_line(&line, Color::Black), Ok(-score));
+    assert_eq!(
+        game.evaluate_line(&[], Color::White),
+        Ok(ai::evaluate(&game, Color::White))
+    );
+    assert!(score > ai::MATERIAL_WEIGHT / 2);
+
+    // black can't move a white ball
+    let line = [line[0], ((8, 8).into(), (8, 8).into(), Dir::NegX)];
+    let err = Error::Selection(SelectionError::WrongTurn((8, 8).into()));
+    assert_eq!(game.evaluate_line(&line, Color::White), Err((1, err)));
+    assert_eq!(game, before);
+}
+
+#[test]
+fn neg_dir() {
+    let pairs = [
+        (Dir::PosX, Dir::NegX),
+        (Dir::PosY, Dir::NegY),
+        (Dir::PosZ, Dir::NegZ),
+    ];
+    for (pos, neg) in pairs {
+        assert_eq!(-pos, neg);
+        assert_eq!(-neg, pos);
+        assert_eq!((-pos).vec(), -pos.vec());
+    }
+}
+
+#[test]
+fn extend_selection_reasons() {
+    let single = [Pos2::from((2, 2)); 2];
+    let pair = [Pos2::from((2, 2)), Pos2::from((3, 3))];
+    let triple = [Pos2::from((2, 2)), Pos2::from((4, 4))];
+
+    let extend = |selection, pos: (i8, i8)| extend_selection(selection, pos.into());
+    assert_eq!(extend(single, (4, 2)), Ok([(2, 2).into(), (4, 2).into()]));
+    assert_eq!(extend(pair, (1, 1)), Ok([(1, 1).into(), (3, 3).into()]));
+    assert_eq!(extend(triple, (3, 3)), Ok([(2, 2).into(), (3, 3).into()]));
+
+    assert_eq!(extend(single, (3, 4)), Err(ExtendError::NotCollinear));
+    assert_eq!(extend(pair, (3, 2)), Err(ExtendError::NotCollinear));
+    assert_eq!(extend(single, (2, 5)), Err(ExtendError::NotAdjacent));
+    assert_eq!(extend(pair, (5, 5)), Err(ExtendError::NotAdjacent));
+    assert_eq!(extend(triple, (5, 5)), Err(ExtendError::WouldExceedThree));
+    assert_eq!(extend(triple, (1, 1)), Err(ExtendError::WouldExceedThree));
+}
+
+#[test]
+fn immobile() {
+    let white = [(0, 0), (1, 0), (8, 8)];
+    let black = [
+        (2, 0),
+        (3, 0),
+        (4, 0),
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 1),
+        (1, 2),
+        (2, 1),
+    ];
+    let game = board(&white, &black);
+
+    // the 2 white balls in the corner can only push too many black ones
+    assert_eq!(game.immobile(Color::White), [(0, 0).into(), (1, 0).into()]);
+    assert_eq!(game.immobile(Color::Black), []);
+
+    // the ball in the middle can only move as part of the line pushing right
+    let white = [
+        (5, 4),
+        (6, 4),
+        (0, 4),
+        (1, 4),
+        (3, 3),
+        (3, 5),
+        (2, 3),
+        (4, 5),
+    ];
+    let game = board(&white, &[(2, 4), (3, 4), (4, 4)]);
+    let middle = Pos2 { x: 3, y: 4 };
+    assert!(crate::DIRS
+        .into_iter()
+        .all(|dir| game.check_single(middle, dir).is_err()));
+    assert!(game.can_ball_move(middle));
+}
+
+#[test]
+fn to_csv() {
+    let game = board_with_reserves(&[(1, 1), (2, 2), (8, 8)], &[(0, 0), (4, 4)]);
+    // balls missing from the board count as pushed off
+    let game = game
+        .then_move((2, 2), (2, 2), Dir::NegZ)
+        .then_move((4, 4), (4, 4), Dir::PosX);
+
+    let csv = game.to_csv();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        rows,
+        [
+            "ply,player,notation,captured,black_off,white_off",
+            "1,white,C3B2,true,5,3",
+            "2,black,E5E6,false,5,3",
+        ]
+    );
+}
+
+#[test]
+fn bitboards() {
+    let game = Abalone::new().then_move((4, 6), (4, 6), Dir::NegZ);
+    let (black, white) = game.bitboards();
+    assert_eq!((black.count_ones(), white.count_ones()), (14, 14));
+    let decoded = Abalone::from_bitboards(black, white, game.turn).unwrap();
+    assert_eq!(decoded, game.clone_position());
+
+    let overlap = Abalone::from_bitboards(0b11, 0b10, Color::White);
+    assert_eq!(overlap, Err(BitboardError::Overlap((1, 0).into())));
+    let too_many = Abalone::from_bitboards(black, white | 1 << 40, Color::White);
+    assert_eq!(too_many, Err(BitboardError::TooMany(Color::White)));
+
+    // (5, 0) is outside of the board
+    let invalid = Abalone::from_bitboards(1 << 5, white, Color::White);
+    assert_eq!(invalid, Err(BitboardError::OutOfBounds(5)));
+    let invalid = Abalone::from_bitboards(1 << 81, white, Color::White);
+    assert_eq!(invalid, Err(BitboardError::OutOfBounds(81)));
+}
+
+#[test]
+fn find_sumito_setups() {
+    let game = board(&[(2, 4), (3, 4), (4, 4), (4, 7)], &[(5, 4), (4, 5), (4, 6)]);
+
+    // 3 white balls against 1 black one, but not the 1 white ball against 2
+    let setups = game.find_sumito_setups(Color::White);
+    assert_eq!(setups, [((2, 4).into(), (2, 4).into(), Dir::PosX)]);
+    assert_eq!(game.find_sumito_setups(Color::Black), []);
+}
+
+#[test]
+fn validate_invariants() {
+    let game = simulate(5, 60, random_policy);
+    assert_eq!(game.validate_invariants(), Ok(()));
+
+    // (8, 0) isn't a cell of the board
+    let mut corrupted = game.clone();
+    corrupted.balls[0][8] = Some(Color::Black);
+    let err = InvariantError::OutOfBounds((8, 0).into());
+    assert_eq!(corrupted.validate_invariants(), Err(err));
+
+    let mut corrupted = Abalone::new();
+    corrupted[(4, 4)] = Some(Color::White);
+    let err = InvariantError::TooManyBalls(Color::White);
+    assert_eq!(corrupted.validate_invariants(), Err(err));
+
+    let mut corrupted = game.clone();
+    corrupted.move_idx = corrupted.moves.len() + 1;
+    assert!(corrupted.validate_invariants().is_err());
+}
+
+#[test]
+fn board_at() {
+    let mut game = simulate(11, 20, random_policy);
+    game.undo_move();
+    let before = game.clone();
+
+    assert_eq!(game.board_at(game.move_idx), game.clone_position());
+    assert_eq!(game.board_at(0), Abalone::new());
+    assert_eq!(game.board_at(usize::MAX), game.clone_position());
+
+    let mut expected = game.clone();
+    for _ in 0..5 {
+        expected.undo_move();
+    }
+    let at = game.board_at(game.move_idx - 5);
+    assert_eq!(at, expected.clone_position());
+    assert_eq!(game, before);
+}
+
+#[test]
+fn occupancy_over_game() {
+    let game = Abalone::new()
+        .then_move((4, 8), (4, 8), Dir::NegY)
+        .then_move((4, 0), (4, 0), Dir::PosY)
+        .then_move((4, 7), (4, 7), Dir::NegY);
+    let occupancy = game.occupancy_over_game();
+
+    // 4 positions with 28 balls each
+    let total: u16 = occupancy.iter().flatten().sum();
+    assert_eq!(total, 4 * 28);
+
+    // the white column advanced into the center
+    assert_eq!(occupancy[4][4], 1);
+    assert_eq!(occupancy[5][4], 3);
+    assert_eq!(occupancy[8][4], 1);
+    // the side corners were never reached
+    assert_eq!(occupancy[4][0], 0);
+    assert_eq!(occupancy[4][8], 0);
+}
+
+#[test]
+fn event_log() {
+    let white = [
+        (1, 1),
+        (2, 2),
+        (4, 8),
+        (5, 8),
+        (6, 8),
+        (7, 8),
+        (8, 8),
+        (5, 7),
+        (6, 7),
+    ];
+    let black = [
+        (0, 0),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 3),
+        (1, 4),
+        (2, 5),
+        (3, 5),
+        (3, 6),
+    ];
+    let mut game = board(&white, &black);
+
+    let first = game.check_move([(2, 2).into(); 2], Dir::PosX).unwrap();
+    game.submit_move(first);
+    game.undo_move();
+    game.redo_move();
+    game.undo_move();
+    // pushing off the ninth black ball wins
+    let capture = game.check_move([(2, 2).into(); 2], Dir::NegZ).unwrap();
+    game.submit_move(capture);
+
+    let events = [
+        GameEvent::Move(first),
+        GameEvent::Undo,
+        GameEvent::Redo,
+        GameEvent::Undo,
+        GameEvent::Move(capture),
+        GameEvent::Outcome(Outcome::Win(Color::White)),
+    ];
+    assert_eq!(game.event_log(), events);
+}
+
+#[test]
+fn first_legal_move() {
+    let game = Abalone::new();
+    let mov = game.first_legal_move().unwrap();
+    assert_eq!(game.validate_move(mov), Ok(()));
+    assert_eq!(game.first_legal_move(), Some(game.legal_moves()[0].3));
+
+    // a lone ball surrounded by opposing ones can't move
+    let game = board(&[(0, 0)], &[(1, 0), (0, 1), (1, 1)]);
+    assert_eq!(game.first_legal_move(), None);
+}
+
+#[test]
+fn notation_orientation() {
+    let pos = Pos2::from((2, 0));
+    assert_eq!(pos.to_notation(), "A3");
+    assert_eq!(pos.to_notation_with(Orientation::Rotated), "I7");
+    assert_eq!(
+        Pos2::from_notation_with("I7", Orientation::Rotated),
+        Some(pos)
+    );
+    assert_eq!(
+        Pos2::from_notation_with("A1", Orientation::Rotated),
+        Some((8, 8).into())
+    );
+
+    for orientation in [Orientation::Standard, Orientation::Rotated] {
+        for (x, y, _) in Abalone::new().iter() {
+            let pos = Pos2::from((x, y));
+            let notation = pos.to_notation_with(orientation);
+            assert_eq!(Pos2::from_notation_with(&notation, orientation), Some(pos));
+        }
+    }
+}
+
+#[test]
+fn valid_selection_geometry() {
+    let check = |first: (i8, i8), last: (i8, i8)| {
+        Abalone::valid_selection_geometry(first.into(), last.into())
+    };
+    assert_eq!(check((2, 2), (4, 4)), Ok(()));
+    assert_eq!(check((4, 4), (4, 2)), Ok(()));
+    assert_eq!(check((3, 3), (3, 3)), Ok(()));
+
+    assert_eq!(check((2, 2), (3, 4)), Err(SelectionError::InvalidSet));
+    assert_eq!(check((2, 2), (5, 2)), Err(SelectionError::TooMany));
+    let out = Pos2::from((8, 0));
+    assert_eq!(check((4, 0), (8, 0)), Err(SelectionError::OutOfBounds(out)));
+}
+
+#[test]
+fn rank_moves() {
+    let game = board_with_reserves(&[(1, 1), (2, 2), (6, 4)], &[(0, 0), (4, 4), (5, 4)]);
+
+    for depth in [1, 2] {
+        let ranked = game.rank_moves(depth);
+        assert_eq!(ranked.len(), game.legal_move_count());
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+        for (_, _, _, mov) in game.legal_moves_iter() {
+            assert!(ranked.iter().any(|&(m, _)| m == mov));
+        }
+
+        // pushing off the black ball in the corner is the best move
+        let (best, score) = ranked[0];
+        assert!(matches!(best, Move::PushedOff { .. }));
+        assert_eq!(score, -ai::score(&game.after(best), depth - 1));
+        if depth == 1 {
+            assert_eq!(score, ai::evaluate(&game.after(best), Color::White));
+        }
+    }
+}
+
+#[test]
+fn u8_encoding() {
+    for (i, dir) in crate::DIRS.into_iter().enumerate() {
+        assert_eq!(Dir::from_u8(dir.to_u8()), Some(dir));
+        assert_eq!(dir.to_u8(), i as u8);
+    }
+    assert_eq!(Dir::from_u8(6), None);
+
+    for (i, color) in [Color::Black, Color::White].into_iter().enumerate() {
+        assert_eq!(color as u8, i as u8);
+        assert_eq!(Color::try_from(color as u8), Ok(color));
+    }
+    assert_eq!(Color::try_from(2), Err(()));
+}
+
+#[test]
+fn captured_from_board() {
+    // the first two black balls are missing
+    let game = Abalone::from_fen("2bbb/bbbbbb/2bbb2/8/9/8/2www2/wwwwww/wwwww b").unwrap();
+    assert_eq!(game.captured_from_board(), (0, 2));
+    assert_eq!(game.scores(), (0, 2));
+    assert_eq!(Abalone::new().captured_from_board(), (0, 0));
+}
+
+#[test]
+fn line() {
+    let game = Abalone::new();
+    let line: Vec<_> = game.line(Pos2 { x: 0, y: 0 }, Dir::PosZ).collect();
+    assert_eq!(line.len(), 9);
+    assert_eq!(line[0], (Pos2 { x: 0, y: 0 }, Some(Color::Black)));
+    assert_eq!(line[4], (Pos2 { x: 4, y: 4 }, None));
+    assert_eq!(line[8], (Pos2 { x: 8, y: 8 }, Some(Color::White)));
+
+    let line: Vec<_> = game.line(Pos2 { x: 4, y: 0 }, Dir::PosX).collect();
+    assert_eq!(line, vec![(Pos2 { x: 4, y: 0 }, Some(Color::Black))]);
+
+    assert_eq!(game.line(Pos2 { x: 5, y: 0 }, Dir::PosY).count(), 0);
+}
+
+#[test]
+fn move_outcome_captured() {
+    let mut game = board_with_reserves(&[(1, 1), (2, 2)], &[(0, 0), (4, 4)]);
+
+    let selection = [Pos2 { x: 2, y: 2 }, Pos2 { x: 1, y: 1 }];
+    let mov = game.check_move(selection, Dir::NegZ).unwrap();
+    let outcome = game.submit_move_with_outcome(mov);
+    assert_eq!(outcome.captured, Some(Color::Black));
+    assert_eq!(game[(0, 0)], Some(Color::White));
+
+    let selection = [Pos2 { x: 4, y: 4 }; 2];
+    let mov = game.check_move(selection, Dir::PosX).unwrap();
+    let outcome = game.submit_move_with_outcome(mov);
+    assert_eq!(outcome.captured, None);
+    assert_eq!(game[(5, 4)], Some(Color::Black));
+}
+
+#[test]
+fn vec2_dot_cross() {
+    let a = Vec2::new(1, 1);
+    let b = Vec2::new(-3, -3);
+    assert_eq!(a.cross(b), 0);
+    assert_eq!(a.dot(b), -6);
+    assert!(a.is_parallel(b));
+
+    let x = Vec2::new(2, 0);
+    let y = Vec2::new(0, 3);
+    assert_eq!(x.dot(y), 0);
+    assert_eq!(x.cross(y), 6);
+    assert_eq!(y.cross(x), -6);
+    assert!(!x.is_parallel(y));
+
+    // doesn't overflow the coordinates
+    let big = Vec2::new(100, -100);
+    assert_eq!(big.dot(big), 20000);
+}
+
+#[test]
+fn solves() {
+    let game = Abalone::new();
+    assert_eq!(game.solves(&game, 0), Some(Vec::new()));
+
+    let mut target = game.clone();
+    let mov = target.first_legal_move().unwrap();
+    target.submit_move(mov);
+    let moves = game.solves(&target, 3).unwrap();
+    assert_eq!(moves, vec![mov]);
+    assert_eq!(game.solves(&target, 0), None);
+
+    // a move of each player can't be reached with a single move
+    let reply = target.first_legal_move().unwrap();
+    target.submit_move(reply);
+    assert_eq!(game.solves(&target, 1), None);
+    let moves = game.solves(&target, 2).unwrap();
+    assert_eq!(moves.len(), 2);
+    let mut replayed = game.clone();
+    for m in moves {
+        replayed.submit_move(m);
+    }
+    assert!(replayed.equals_ignoring_turn(&target));
+}
+
+#[test]
+fn with_turn() {
+    let game = Abalone::new().with_turn(Color::Black);
+    assert_eq!(game.turn, Color::Black);
+    assert!(game.moves.is_empty());
+    assert_eq!(game.move_idx, 0);
+
+    let daisy = Abalone::with_start_position(StartPosition::GermanDaisy).with_turn(Color::Black);
+    assert_eq!(daisy.turn, Color::Black);
+    assert_eq!(
+        daisy.is_starting_position(),
+        Some(StartPosition::GermanDaisy)
+    );
+    assert!(daisy.moves.is_empty());
+}
+
+#[test]
+fn game_over() {
+    let fen = "b4/1w4/2w4/8/bbbbbbbb1/8/4w2/wwwwww/wwwww w";
+    let mut game = Abalone::from_fen(fen).unwrap();
+    assert_eq!(game.scores(), (0, 5));
+    assert_eq!(game.winner(), None);
+
+    let capture = game
+        .check_game_move([(2, 2).into(), (1, 1).into()], Dir::NegZ)
+        .unwrap();
+    game.submit_move(capture);
+    assert_eq!(game.winner(), Some(Color::White));
+
+    let selection = [(0, 4).into(); 2];
+    let err = game.check_game_move(selection, Dir::PosZ);
+    assert_eq!(err, Err(Error::GameOver(Outcome::Win(Color::White))));
+    assert_eq!(
+        game.parse_move("E1F2"),
+        Err(Error::GameOver(Outcome::Win(Color::White)))
+    );
+    let mov = game.check_move(selection, Dir::PosZ).unwrap();
+    let err = ApplyError::Illegal(Error::GameOver(Outcome::Win(Color::White)));
+    assert_eq!(game.apply_many(&[mov]), Err((0, err)));
+    let before = game.clone();
+    game.submit_move(mov);
+    assert_eq!(game, before);
+
+    game.undo_move();
+    assert_eq!(game.winner(), None);
+    assert_eq!(
+        game.check_game_move([(2, 2).into(); 2], Dir::NegZ),
+        Ok(capture)
+    );
+    game.redo_move();
+    assert_eq!(game.winner(), Some(Color::White));
+
+    // draws end the game as well
+    let mut game = Abalone::new();
+    let selection = [(4, 6).into(); 2];
+    game.agree_draw();
+    let err = Error::GameOver(Outcome::Draw(DrawReason::Agreement));
+    assert_eq!(game.check_game_move(selection, Dir::NegZ), Err(err.clone()));
+    // so do copies of the position
+    let copy = game.clone_position();
+    assert_eq!(copy.check_game_move(selection, Dir::NegZ), Err(err));
+    let mut game = Abalone::new();
+    game.rules.move_limit = Some(0);
+    let err = Error::GameOver(Outcome::Draw(DrawReason::MoveLimit));
+    assert_eq!(game.check_game_move(selection, Dir::NegZ), Err(err));
+}
+
+#[test]
+fn status() {
+    let mut game = Abalone::new();
+    assert_eq!(game.status(), GameStatus::Ongoing { turn: Color::White });
+    assert!(!game.status().is_terminal());
+
+    game.rules.move_limit = Some(1);
+    let mov = game.first_legal_move().unwrap();
+    game.submit_move(mov);
+    assert_eq!(game.status(), GameStatus::Draw);
+    assert!(game.status().is_terminal());
+    game.undo_move();
+    assert_eq!(game.status(), GameStatus::Ongoing { turn: Color::White });
+
+    let fen = "b4/1w4/2w4/8/bbbbbbbb1/8/4w2/wwwwww/wwwww w";
+    let mut game = Abalone::from_fen(fen).unwrap();
+    let capture = game.check_move([(2, 2).into(); 2], Dir::NegZ).unwrap();
+    game.submit_move(capture);
+    let win = GameStatus::Win {
+        winner: Color::White,
+    };
+    assert_eq!(game.status(), win);
+    assert!(game.status().is_terminal());
+    game.undo_move();
+    assert_eq!(game.status(), GameStatus::Ongoing { turn: Color::White });
+    game.redo_move();
+    assert_eq!(game.status(), win);
+}
+
+#[test]
+fn perft() {
+    let mut game = Abalone::new();
+    assert_eq!(game.perft(0), 1);
+    assert_eq!(game.perft(1), 44);
+    // the sides don't interact within the first two moves
+    assert_eq!(game.perft(2), 44 * 44);
+
+    let before = game.clone();
+    let count = game.perft(3);
+    assert!(count > 44 * 44);
+    assert_eq!(game, before);
+    assert_eq!(game.zobrist_hash(), before.zobrist_hash());
+}
+
+#[test]
+fn best_move() {
+    // pushing off the black ball in the corner wins
+    let fen = "b4/1w4/2w4/8/bbbbbbbb1/8/4w2/wwwwww/wwwww w";
+    let game = Abalone::from_fen(fen).unwrap();
+    let capture = game.check_move([(2, 2).into(); 2], Dir::NegZ).unwrap();
+
+    let before = game.clone();
+    for depth in 1..=3 {
+        assert_eq!(ai::best_move(&game, depth), Some(capture));
+        assert_eq!(game, before);
+    }
+
+    let config = SearchConfig {
+        depth: 2,
+        prefer_push_off: false,
+        ..Default::default()
+    };
+    assert_eq!(ai::best_move_with(&game, &config), Some(capture));
+
+    let opening = Abalone::new();
+    let mov = ai::best_move(&opening, 2).unwrap();
+    assert!(opening.validate_move(mov).is_ok());
+
+    // white has no balls left to move
+    let mut game = Abalone::new();
+    for (pos, c) in game.iter_mut() {
+        if pos.y > 4 {
+            *c = None;
+        }
+    }
+    assert_eq!(ai::best_move(&game, 2), None);
+}
+
+#[test]
+fn evaluate() {
+    let game = Abalone::new();
+    assert_eq!(ai::evaluate(&game, Color::Black), 0);
+
+    let game = board(&[(0, 0)], &[(4, 4), (5, 4)]);
+    let black = ai::evaluate(&game, Color::Black);
+    assert_eq!(black, -ai::evaluate(&game, Color::White));
+    // one more ball on the board, in the center and next to each other
+    let positional = ai::CENTRALITY_WEIGHT * (4 + 3) + ai::COHESION_WEIGHT * 2;
+    assert_eq!(black, ai::MATERIAL_WEIGHT + positional);
+}
+
+#[test]
+fn zobrist_hash_is_stable() {
+    assert_eq!(crate::ZOBRIST_SEED, 0x9E37_79B9_7F4A_7C15);
+
+    let game = Abalone::new();
+    // changing the keys breaks hashes shared with peers
+    assert_eq!(game.zobrist_hash(), 0x6540_ddec_8d03_3301);
+    let black = game.clone().with_turn(Color::Black);
+    assert_ne!(game.zobrist_hash(), black.zobrist_hash());
+}
+
+#[test]
+fn repetition() {
+    let mut game = Abalone::new();
+    assert_eq!(game.repetition_count(), 1);
+
+    let shuffle = [
+        ((4, 6), Dir::NegY),
+        ((2, 2), Dir::PosY),
+        ((4, 5), Dir::PosY),
+        ((2, 3), Dir::NegY),
+    ];
+    for round in 0..2 {
+        for (p, dir) in shuffle {
+            let mov = game.check_move([p.into(); 2], dir).unwrap();
+            game.submit_move(mov);
+        }
+        assert_eq!(game.repetition_count(), round + 2);
+    }
+    assert!(game.is_threefold_repetition());
+
+    game.undo_move();
+    assert_eq!(game.repetition_count(), 2);
+    assert!(!game.is_threefold_repetition());
+    game.redo_move();
+    assert!(game.is_threefold_repetition());
+}
+
+#[test]
+fn notation_off_board() {
+    assert_eq!(Pos2::from_notation("A1"), Some((0, 0).into()));
+    assert_eq!(Pos2::from_notation("e5"), Some((4, 4).into()));
+    assert_eq!(Pos2::from_notation("I9"), Some((8, 8).into()));
+    // cells cut off by the hexagonal shape
+    for s in ["A6", "A9", "E0", "I1", "I4", "D9"] {
+        assert_eq!(Pos2::from_notation(s), None, "{s}");
+    }
+    for s in ["", "A", "J1", "A10", "5E", "E-5"] {
+        assert_eq!(Pos2::from_notation(s), None, "{s}");
+    }
+}
+
+#[test]
+fn parse_move() {
+    let mut game = Abalone::new();
+    let expected = game.check_move([(4, 6).into(); 2], Dir::NegY).unwrap();
+    let mov = game.parse_move("G5F5").unwrap();
+    assert_eq!(mov, expected);
+    assert_eq!(game.parse_move(&mov.to_notation()), Ok(mov));
+    game.submit_move(mov);
+
+    // broadside
+    let mov = game.parse_move("C3-C5-D3").unwrap();
+    assert_eq!(mov.to_notation(), "C3C5D3");
+    assert_eq!(game.parse_move(&mov.to_notation()), Ok(mov));
+
+    // there are legal moves
+    assert_eq!(game.parse_move("pass"), Err(Error::PassNotAllowed));
+    assert_eq!(
+        game.parse_move("C3"),
+        Err(Error::InvalidNotation("C3".to_string()))
+    );
+    assert_eq!(
+        game.parse_move("J1J2"),
+        Err(Error::InvalidNotation("J1J2".to_string()))
+    );
+    // the same errors as check_move
+    let selection = [(3, 2).into(); 2];
+    assert_eq!(
+        game.parse_move("C4B3"),
+        game.check_move(selection, Dir::NegZ)
+    );
+    assert!(game.parse_move("C4B3").is_err());
+    assert!(matches!(
+        game.parse_move("H5G5"),
+        Err(Error::Selection(SelectionError::WrongTurn(_)))
+    ));
+}
+
+#[test]
+fn snakes() {
+    let standard = Abalone::with_start_position(StartPosition::Standard);
+    assert_eq!(Abalone::new(), standard);
+
+    let game = Abalone::with_start_position(StartPosition::Snakes);
+    assert_eq!(game.turn, Color::White);
+    assert_eq!(game.is_starting_position(), Some(StartPosition::Snakes));
+    for (x, y, c) in game.iter() {
+        let rotated = game[(8 - x, 8 - y)].map(|c| c.opposite());
+        assert_eq!(c, rotated, "({x}, {y})");
+    }
+}
+
+#[test]
+fn from_balls() {
+    let opening = Abalone::new();
+    let game = Abalone::from_balls(opening.balls, Color::Black).unwrap();
+    assert_eq!(game.balls, opening.balls);
+    assert_eq!(game.turn, Color::Black);
+    assert!(game.moves.is_empty());
+    assert_eq!(game.move_idx, 0);
+
+    let mut balls = opening.balls;
+    balls[0][8] = Some(Color::White);
+    let res = Abalone::from_balls(balls, Color::White);
+    assert_eq!(res, Err(SetupError::OutOfBounds((8, 0).into())));
+
+    let mut balls = opening.balls;
+    balls[4][4] = Some(Color::Black);
+    let res = Abalone::from_balls(balls, Color::White);
+    assert_eq!(res, Err(SetupError::TooManyBalls(Color::Black)));
+}
+
+#[test]
+fn reset_and_jump_to() {
+    let mut game = simulate(6, 5, random_policy);
+    let end = game.clone();
+    let start = game.board_at(0);
+
+    game.reset();
+    assert_eq!(game.move_idx, 0);
+    assert_eq!(game.moves, end.moves);
+    assert_eq!(game.balls, start.balls);
+    assert_eq!(game.turn, start.turn);
+    assert_eq!(game.event_log().last(), Some(&GameEvent::Reset));
+
+    // nothing to reset
+    let num_events = game.event_log().len();
+    game.reset();
+    assert_eq!(game.event_log().len(), num_events);
+
+    game.jump_to(4);
+    assert_eq!(game.move_idx, 4);
+    assert_eq!(game.balls, end.board_at(4).balls);
+    game.jump_to(2);
+    assert_eq!(game.balls, end.board_at(2).balls);
+    game.jump_to(100);
+    assert_eq!(game, end);
+
+    let num_events = game.event_log().len();
+    game.jump_to(6);
+    assert_eq!(game.event_log().len(), num_events);
+}
+
+#[test]
+fn move_diff() {
+    let game = Abalone::new();
+    // broadside move of 3 balls
+    let mov = game
+        .check_move([(4, 6).into(), (6, 6).into()], Dir::NegY)
+        .unwrap();
+    let diff = game.move_diff(mov);
+    assert_eq!(diff.len(), 6);
+    for x in 4..7 {
+        let white = Some(Color::White);
+        assert!(diff.contains(&((x, 6).into(), white, None)));
+        assert!(diff.contains(&((x, 5).into(), None, white)));
+    }
+    let changes: Vec<_> = diff.iter().copied().collect();
+    assert_eq!(game.board_delta(mov).changes, changes);
+
+    // inline move of 3 balls only changes both ends
+    let mov = game
+        .check_move([(4, 8).into(), (4, 6).into()], Dir::NegY)
+        .unwrap();
+    let diff = game.move_diff(mov);
+    assert_eq!(diff.len(), 2);
+    assert!(diff.contains(&((4, 8).into(), Some(Color::White), None)));
+    assert!(diff.contains(&((4, 5).into(), None, Some(Color::White))));
+}
+
+#[test]
+fn pushed_off_marbles() {
+    let fen = "b4/1w4/2w4/8/bbbbbbbb1/8/4w2/wwwwww/wwwww w";
+    let game = Abalone::from_fen(fen).unwrap();
+    let capture = game.check_move([(2, 2).into(); 2], Dir::NegZ).unwrap();
+    let pushed_off: Vec<_> = capture.pushed_off_marbles().iter().copied().collect();
+    assert_eq!(pushed_off, vec![Pos2::from((0, 0))]);
+
+    let mov = game.check_move([(2, 2).into(); 2], Dir::PosX).unwrap();
+    assert!(mov.pushed_off_marbles().is_empty());
+    assert!(Move::Pass.pushed_off_marbles().is_empty());
+}
+
+#[test]
+fn std_error() {
+    fn check(game: &Abalone) -> Result<Move, Box<dyn std::error::Error>> {
+        Ok(game.check_move([(0, 0).into(); 2], Dir::PosX)?)
+    }
+    let err = check(&Abalone::new()).unwrap_err();
+    let source = err.source().unwrap();
+    assert_eq!(
+        source.to_string(),
+        SelectionError::WrongTurn((0, 0).into()).to_string()
+    );
+
+    let err = ApplyError::Illegal(Error::Move(MoveError::BlockedByOwn((1, 1).into())));
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.source().is_some());
+}
+
+#[test]
+fn pos2_ord_hash() {
+    let mut cells: Vec<Pos2> = Abalone::new()
+        .iter()
+        .map(|(x, y, _)| (x, y).into())
+        .collect();
+    let row_major = cells.clone();
+    cells.reverse();
+    cells.sort();
+    assert_eq!(cells, row_major);
+    assert!(Pos2::from((8, 0)) < Pos2::from((0, 1)));
+
+    let set: std::collections::HashSet<Pos2> = cells.iter().copied().collect();
+    assert_eq!(set.len(), 61);
+    let dirs: std::collections::HashSet<Dir> = crate::DIRS.into_iter().collect();
+    assert_eq!(dirs.len(), 6);
+}
+
+#[test]
+fn stackvec_pop() {
+    // popping used to read the slot past the last element, which is out of
+    // bounds for a full vec and uninitialized otherwise
+    let mut vec: StackVec<2, String> = StackVec::new();
+    vec.push("a".to_string());
+    vec.push("b".to_string());
+    assert_eq!(vec.pop(), Some("b".to_string()));
+    assert_eq!(vec.len(), 1);
+    assert_eq!(vec.pop(), Some("a".to_string()));
+    assert_eq!(vec.pop(), None);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn serde_errors() {
+    let errors = [
+        Error::Selection(SelectionError::MixedSet(
+            [(1, 2).into(), (2, 3).into()].into(),
+        )),
+        Error::Move(MoveError::PushedOff([(0, 0).into()].into())),
+        Error::Move(MoveError::TooManyOpposing {
+            first: (3, 3).into(),
+            last: (4, 4).into(),
+        }),
+        Error::GameOver(Outcome::Win(Color::Black)),
+    ];
+    for error in errors {
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(serde_json::from_str::<Error>(&json).unwrap(), error);
+    }
+
+    let vec = Vec2::new(-1, 2);
+    let json = serde_json::to_string(&vec).unwrap();
+    assert_eq!(serde_json::from_str::<Vec2>(&json).unwrap(), vec);
+}
+
+#[test]
+fn stackvec_try_push() {
+    let mut vec: StackVec<2, String> = StackVec::new();
+    assert_eq!(vec.try_push("a".to_string()), Ok(()));
+    assert_eq!(vec.try_push("b".to_string()), Ok(()));
+    assert_eq!(vec.try_push("c".to_string()), Err("c".to_string()));
+    assert_eq!(vec.len(), 2);
+
+    assert_eq!(vec.pop(), Some("b".to_string()));
+    assert_eq!(vec.try_push("c".to_string()), Ok(()));
+    assert_eq!(vec.pop(), Some("c".to_string()));
+    assert_eq!(vec.pop(), Some("a".to_string()));
+    assert_eq!(vec.pop(), None);
+}
+
+#[test]
+fn stackvec_serde() {
+    let vec: StackVec<3, Pos2> = [(1, 2).into(), (2, 3).into(), (3, 4).into()].into();
+    let json = serde_json::to_string(&vec).unwrap();
+    assert_eq!(json, r#"[{"x":1,"y":2},{"x":2,"y":3},{"x":3,"y":4}]"#);
+    assert_eq!(
+        serde_json::from_str::<StackVec<3, Pos2>>(&json).unwrap(),
+        vec
+    );
+
+    let empty: StackVec<3, Pos2> = serde_json::from_str("[]").unwrap();
+    assert!(empty.is_empty());
+
+    // more elements than fit
+    let res = serde_json::from_str::<StackVec<2, Pos2>>(&json);
+    assert!(res.is_err());
+}
+
+#[test]
+fn neighbors() {
+    let center: Vec<Pos2> = Pos2::from((4, 4)).neighbors().collect();
+    assert_eq!(center.len(), 6);
+    assert!(center.iter().all(|&p| (p - Pos2::from((4, 4))).mag() == 1));
+
+    let corner: Vec<Pos2> = Pos2::from((0, 0)).neighbors().collect();
+    assert_eq!(corner.len(), 3);
+    assert!(corner.contains(&(1, 1).into()));
+
+    assert_eq!(Pos2::from((2, 0)).neighbors().count(), 4);
+    for cell in crate::edge_cells() {
+        assert!(cell.neighbors().count() < 6);
+    }
+}
+
+#[test]
+fn rotate_and_canonical_hash() {
+    let center = Pos2::from((4, 4));
+    assert_eq!(center.rotate(1), center);
+    assert_eq!(Pos2::from((5, 4)).rotate(1), (5, 5).into());
+    assert_eq!(Pos2::from((0, 0)).rotate(3), (8, 8).into());
+    for (x, y, _) in Abalone::new().iter() {
+        let pos = Pos2::from((x, y));
+        assert!(crate::is_in_bounds(pos.rotate(1)));
+        assert_eq!(pos.rotate(6), pos);
+        assert_eq!(pos.rotate(2).rotate(4), pos);
+    }
+
+    // the same position rotated by 180 degrees
+    let game = Abalone::new().then_move((4, 6), (4, 6), Dir::NegZ);
+    let mut rotated = Abalone::new();
+    for (x, y, c) in game.iter() {
+        let pos = Pos2::from((x, y)).rotate(3);
+        rotated[pos] = c;
+    }
+    rotated.turn = game.turn;
+    assert_ne!(rotated.zobrist_hash(), game.zobrist_hash());
+    assert_eq!(rotated.canonical_hash(), game.canonical_hash());
+
+    let mut reflected = Abalone::new();
+    for (x, y, c) in game.iter() {
+        reflected[(y, x)] = c;
+    }
+    reflected.turn = game.turn;
+    assert_eq!(reflected.canonical_hash(), game.canonical_hash());
+
+    let other_turn = game.clone().with_turn(Color::White);
+    assert_ne!(other_turn.canonical_hash(), game.canonical_hash());
+}
+
+#[test]
+fn board_cells() {
+    let cells: Vec<Pos2> = crate::board_cells().collect();
+    assert_eq!(cells.len(), 61);
+    assert!(cells.iter().all(|&p| crate::is_in_bounds(p)));
+    assert!(cells.windows(2).all(|w| w[0] < w[1]));
+    let iterated: Vec<Pos2> = Abalone::new()
+        .iter()
+        .map(|(x, y, _)| (x, y).into())
+        .collect();
+    assert_eq!(cells, iterated);
+}
+
+#[test]
+fn dir_rotation() {
+    let all = Dir::all();
+    assert_eq!(all.len(), 6);
+    for dir in all {
+        assert_eq!(dir.rotate_cw().rotate_ccw(), dir);
+        assert_eq!(dir.rotate_cw().vec(), dir.vec().rotate_60());
+        assert_eq!(dir.rotate_cw().rotate_cw().rotate_cw(), dir.opposite());
+        assert_eq!(-dir, dir.opposite());
+        assert!(all.iter().filter(|&&d| d == dir).count() == 1);
+    }
+    assert_eq!(Dir::PosX.rotate_cw(), Dir::PosZ);
+    assert_eq!(Dir::PosX.rotate_ccw(), Dir::NegY);
+}
+
+#[test]
+fn event_log_max_history() {
+    let mut game = Abalone::new();
+    game.rules.max_history = Some(2);
+    let mut events = Vec::new();
+    for _ in 0..3 {
+        let mov = game.first_legal_move().unwrap();
+        game.submit_move(mov);
+        events.push(GameEvent::Move(mov));
+    }
+    game.undo_move();
+    game.redo_move();
+    events.extend([GameEvent::Undo, GameEvent::Redo]);
+
+    // the oldest move was dropped from the history, but not from the log, so
+    // it can still be replayed from the start
+    assert_eq!(game.moves.len(), 2);
+    assert_eq!(game.event_log(), events);
+}
+
+#[test]
+fn best_move_epsilon() {
+    let game = Abalone::new();
+    let best = ai::best_move(&game, 1).unwrap();
+    let config = SearchConfig {
+        depth: 1,
+        seed: 7,
+        ..Default::default()
+    };
+    assert_eq!(ai::best_move_with(&game, &config), Some(best));
+
+    // the opening is symmetric, so there are several equally good moves
+    let best_score = ai::evaluate(&game.after(best), Color::White);
+    let mut chosen = Vec::new();
+    for seed in 0..16 {
+        let config = SearchConfig {
+            epsilon: 1,
+            seed,
+            ..config
+        };
+        let mov = ai::best_move_with(&game, &config).unwrap();
+        assert_eq!(ai::best_move_with(&game, &config), Some(mov));
+        assert!(ai::evaluate(&game.after(mov), Color::White) >= best_score - 1);
+        if !chosen.contains(&mov) {
+            chosen.push(mov);
+        }
+    }
+    assert!(chosen.len() > 1);
+}
@@ -0,0 +1,58 @@
+use std::fmt::Write;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Abalone, ApplyError, Move, StartPosition};
+
+/// A game recorded as its start position and the moves played from there.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub start: StartPosition,
+    pub moves: Vec<Move>,
+}
+
+impl SavedGame {
+    pub fn new(start: StartPosition) -> Self {
+        Self {
+            start,
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl Abalone {
+    /// Replays the moves of a saved game onto its start position.
+    ///
+    /// Every move is validated against the board it is applied to, on failure
+    /// the index of the offending move is returned alongside the error.
+    pub fn from_transcript(saved: &SavedGame) -> Result<Self, (usize, ApplyError)> {
+        let mut game = Abalone::with_start_position(saved.start);
+        game.apply_many(&saved.moves)?;
+        Ok(game)
+    }
+
+    /// The played moves as comma separated values, with a header and one row
+    /// per move containing the ply, the player, the move in
+    /// [`crate::notation`], whether it pushed off a ball, and the number of
+    /// black and white balls off the board after it.
+    ///
+    /// Moves dropped from the history aren't included, but the plies are still
+    /// counted from the start of the game.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("ply,player,notation,captured,black_off,white_off\n");
+        let moves = &self.moves[..self.move_idx];
+        for (&mov, (ply, game)) in moves.iter().zip(self.replay().skip(1)) {
+            let player = game.turn.opposite();
+
+            // a color's score is the number of opposing balls off the board
+            let (black_score, white_score) = game.scores();
+            let notation = mov.to_notation();
+            let captured = matches!(mov, Move::PushedOff { .. });
+            _ = writeln!(
+                csv,
+                "{ply},{player},{notation},{captured},{white_score},{black_score}"
+            );
+        }
+        csv
+    }
+}
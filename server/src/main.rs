@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::sync::Arc;
 
-use abalone_core::{dto, Abalone, Color};
+use abalone_core::{dto, Abalone, Color, Dir, Move, Pos2};
 use async_channel::{Receiver, Sender};
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::extract::{Path, State};
@@ -45,6 +45,8 @@ struct Room {
     players: [Option<PlayerSession>; 2],
     transactions: HashMap<TransactionId, JoinRoomTransaction>,
     undo_requested: bool,
+    /// The color of the player that offered a draw.
+    draw_offer: Option<Color>,
 }
 
 #[derive(Clone, Debug)]
@@ -197,6 +199,7 @@ async fn receiver_task(
                     players: [Some(session.clone()), None],
                     transactions: HashMap::new(),
                     undo_requested: false,
+                    draw_offer: None,
                 };
                 let dto = dto::Room::from(&new_room);
                 let new_room = Arc::new(RwLock::new(new_room));
@@ -276,7 +279,7 @@ async fn receiver_task(
             }
             ClientMsg::AllowJoinRoom(transaction_id) => {
                 let Some(r) = &mut room else {
-                    let error = format!("Not inside a room");
+                    let error = "Not inside a room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 };
@@ -291,7 +294,7 @@ async fn receiver_task(
                 if transaction.player.sender.is_closed() {
                     room_lock.transactions.remove(&transaction_id);
 
-                    let error = format!("Player already disconnected");
+                    let error = "Player already disconnected".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 }
@@ -353,19 +356,19 @@ async fn receiver_task(
             }
             ClientMsg::LeaveRoom => {
                 let Some(r) = &room else {
-                    let error = format!("Not inside a room");
+                    let error = "Not inside a room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 };
 
-                leave_room(&state, &r).await;
+                leave_room(&state, r).await;
 
                 room = None;
                 send_msg(&session.sender, ServerMsg::SyncEmpty).await;
             }
             ClientMsg::MakeMove { first, last, dir } => {
                 let Some(r) = &room else {
-                    let error = format!("Not inside a room");
+                    let error = "Not inside a room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 };
@@ -373,30 +376,31 @@ async fn receiver_task(
                 let mut room_lock = r.room.write().await;
                 let player_color = Color::try_from(r.player_idx as u8)
                     .expect("player_idx should always be 0 or 1");
-                if room_lock.game.turn != player_color {
-                    let error = format!("It's not {player_color}s turn");
-                    send_msg(&session.sender, ServerMsg::Error(error)).await;
+                let msg = make_move(&mut room_lock.game, player_color, [first, last], dir);
+                if let ServerMsg::Error(_) = msg {
+                    send_msg(&session.sender, msg).await;
                     continue 'session;
                 }
+                room_lock.undo_requested = false;
+                room_lock.draw_offer = None;
 
-                match room_lock.game.check_move([first, last], dir) {
-                    Ok(m) => {
-                        room_lock.game.submit_move(m);
-                        room_lock.undo_requested = false;
-
-                        for p in room_lock.players.iter().filter_map(|p| p.as_ref()) {
-                            send_msg(&p.sender, ServerMsg::AppliedMove(m)).await;
-                        }
-                    }
-                    Err(e) => {
-                        let error = format!("Invalid move: {e}");
-                        send_msg(&session.sender, ServerMsg::Error(error)).await;
-                    }
+                for p in room_lock.players.iter().filter_map(|p| p.as_ref()) {
+                    send_msg(&p.sender, msg.clone()).await;
                 }
             }
+            ClientMsg::Pass => {
+                let Some(r) = &room else {
+                    let error = "Not inside a room".to_string();
+                    send_msg(&session.sender, ServerMsg::Error(error)).await;
+                    continue 'session;
+                };
+
+                let room_lock = r.room.read().await;
+                send_msg(&session.sender, reject_pass(&room_lock.game)).await;
+            }
             ClientMsg::RequestUndo => {
                 let Some(r) = &room else {
-                    let error = format!("Not inside a room");
+                    let error = "Not inside a room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 };
@@ -404,13 +408,7 @@ async fn receiver_task(
                 let mut room_lock = r.room.write().await;
                 let player_color = Color::try_from(r.player_idx as u8)
                     .expect("player_idx should always be 0 or 1");
-                if room_lock.game.turn == player_color {
-                    let error = format!("Can't request undo for opponents move");
-                    send_msg(&session.sender, ServerMsg::Error(error)).await;
-                    continue 'session;
-                }
-                if !room_lock.game.can_undo() {
-                    let error = format!("No move to undo");
+                if let Err(error) = request_undo(&room_lock.game, player_color) {
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 }
@@ -429,14 +427,14 @@ async fn receiver_task(
                 }
 
                 if !sent_request {
-                    let error = format!("No other player in room");
+                    let error = "No other player in room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 }
             }
             ClientMsg::AllowUndo => {
                 let Some(r) = &room else {
-                    let error = format!("Not inside a room");
+                    let error = "Not inside a room".to_string();
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 };
@@ -444,23 +442,64 @@ async fn receiver_task(
                 let mut room_lock = r.room.write().await;
                 let player_color = Color::try_from(r.player_idx as u8)
                     .expect("player_idx should always be 0 or 1");
-                if room_lock.game.turn != player_color {
-                    let error = format!("Can't allow undo for own move");
-                    send_msg(&session.sender, ServerMsg::Error(error)).await;
+                let undo_requested = room_lock.undo_requested;
+                let msg = allow_undo(&mut room_lock.game, player_color, undo_requested);
+                if let ServerMsg::Error(_) = msg {
+                    send_msg(&session.sender, msg).await;
                     continue 'session;
                 }
-                if !room_lock.game.can_undo() {
-                    let error = format!("No move to undo");
+                room_lock.undo_requested = false;
+
+                for p in room_lock.players.iter().filter_map(|p| p.as_ref()) {
+                    send_msg(&p.sender, msg.clone()).await;
+                }
+            }
+            ClientMsg::OfferDraw => {
+                let Some(r) = &room else {
+                    let error = "Not inside a room".to_string();
+                    send_msg(&session.sender, ServerMsg::Error(error)).await;
+                    continue 'session;
+                };
+
+                let mut room_lock = r.room.write().await;
+                let player_color = Color::try_from(r.player_idx as u8)
+                    .expect("player_idx should always be 0 or 1");
+                if let Err(error) = offer_draw(&room_lock.game) {
                     send_msg(&session.sender, ServerMsg::Error(error)).await;
                     continue 'session;
                 }
 
-                room_lock.game.undo_move();
-                room_lock.undo_requested = false;
+                room_lock.draw_offer = Some(player_color);
+
+                for (i, p) in room_lock.players.iter().enumerate() {
+                    if i == r.player_idx {
+                        continue;
+                    }
+                    if let Some(p) = p {
+                        send_msg(&p.sender, ServerMsg::DrawOffered).await;
+                    }
+                }
+            }
+            ClientMsg::AnswerDraw { accept } => {
+                let Some(r) = &room else {
+                    let error = "Not inside a room".to_string();
+                    send_msg(&session.sender, ServerMsg::Error(error)).await;
+                    continue 'session;
+                };
+
+                let mut room_lock = r.room.write().await;
+                let player_color = Color::try_from(r.player_idx as u8)
+                    .expect("player_idx should always be 0 or 1");
+                let draw_offer = room_lock.draw_offer;
+                let msg = answer_draw(&mut room_lock.game, draw_offer, player_color, accept);
+                if let ServerMsg::Error(_) = msg {
+                    send_msg(&session.sender, msg).await;
+                    continue 'session;
+                }
+                room_lock.draw_offer = None;
 
                 for p in room_lock.players.iter().filter_map(|p| p.as_ref()) {
-                    let dto = dto::Room::from(&*room_lock);
-                    send_msg(&p.sender, ServerMsg::Sync(dto)).await;
+                    send_msg(&p.sender, msg.clone()).await;
                 }
             }
         }
@@ -469,6 +508,91 @@ async fn receiver_task(
     session.sender.close();
 }
 
+/// Checks and submits the move of `player`, and returns the message notifying
+/// the players about it. Moves are rejected once the game is won or drawn.
+fn make_move(game: &mut Abalone, player: Color, selection: [Pos2; 2], dir: Dir) -> ServerMsg {
+    if game.turn != player {
+        return ServerMsg::Error(format!("It's not {player}s turn"));
+    }
+    match game.check_game_move(selection, dir) {
+        Ok(m) => submit_move(game, m),
+        Err(e) => ServerMsg::Error(format!("Invalid move: {e}")),
+    }
+}
+
+/// Submits the move and returns the message notifying the players about it.
+fn submit_move(game: &mut Abalone, mov: Move) -> ServerMsg {
+    let move_outcome = game.submit_move_with_outcome(mov);
+    ServerMsg::AppliedMove(mov, move_outcome, game.outcome())
+}
+
+/// Checks whether `player` may request undoing the last move, which has to be
+/// their own.
+fn request_undo(game: &Abalone, player: Color) -> Result<(), String> {
+    if game.turn == player {
+        return Err("Can't request undo for opponents move".to_string());
+    }
+    if !game.can_undo() {
+        return Err("No move to undo".to_string());
+    }
+    Ok(())
+}
+
+/// Undoes the last move if the opponent of `player` requested it, and returns
+/// the message notifying the players about it.
+fn allow_undo(game: &mut Abalone, player: Color, undo_requested: bool) -> ServerMsg {
+    if game.turn != player {
+        return ServerMsg::Error("Can't allow undo for own move".to_string());
+    }
+    if !undo_requested {
+        return ServerMsg::Error("No undo was requested".to_string());
+    }
+    match game.undo_move_with_delta() {
+        Some((mov, delta)) => ServerMsg::UndoneMove(mov, delta, game.turn),
+        None => ServerMsg::Error("No move to undo".to_string()),
+    }
+}
+
+/// Checks whether a draw may be offered, which is only possible while the game
+/// is ongoing.
+fn offer_draw(game: &Abalone) -> Result<(), String> {
+    if game.outcome().is_some() {
+        return Err("The game is already finished".to_string());
+    }
+    Ok(())
+}
+
+/// Accepts or declines the draw offered by the opponent of `player`, and
+/// returns the message notifying the players about it.
+fn answer_draw(
+    game: &mut Abalone,
+    draw_offer: Option<Color>,
+    player: Color,
+    accept: bool,
+) -> ServerMsg {
+    if draw_offer != Some(player.opposite()) {
+        return ServerMsg::Error("No draw was offered by the opponent".to_string());
+    }
+    if game.outcome().is_some() {
+        return ServerMsg::Error("The game is already finished".to_string());
+    }
+    if !accept {
+        return ServerMsg::DrawDeclined;
+    }
+    game.agree_draw();
+    ServerMsg::DrawAgreed
+}
+
+/// Returns the message rejecting a pass, a player has to move if able to.
+fn reject_pass(game: &Abalone) -> ServerMsg {
+    let error = if game.must_move() {
+        "Passing isn't allowed, a move has to be made"
+    } else {
+        "There isn't any legal move, but passing isn't allowed either"
+    };
+    ServerMsg::Error(error.to_string())
+}
+
 async fn sender_task(mut socket: SplitSink<WebSocket, Message>, session: Receiver<ServerMsg>) {
     loop {
         let Ok(msg) = session.recv().await else {
@@ -522,8 +646,8 @@ impl From<&Room> for dto::Room {
             name: room.name.clone(),
             game: room.game.clone(),
             players: [
-                room.players[0].as_ref().map(|p| dto::User::from(p)),
-                room.players[1].as_ref().map(|p| dto::User::from(p)),
+                room.players[0].as_ref().map(dto::User::from),
+                room.players[1].as_ref().map(dto::User::from),
             ],
         }
     }
@@ -535,8 +659,8 @@ impl From<&Room> for dto::OpenRoom {
             id: room.id,
             name: room.name.clone(),
             players: [
-                room.players[0].as_ref().map(|p| dto::User::from(p)),
-                room.players[1].as_ref().map(|p| dto::User::from(p)),
+                room.players[0].as_ref().map(dto::User::from),
+                room.players[1].as_ref().map(dto::User::from),
             ],
         }
     }
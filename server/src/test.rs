@@ -1 +1,152 @@
+use abalone_core::dto::ServerMsg;
+use abalone_core::{Abalone, Color, Dir, DrawReason, Move, Outcome};
 
+use crate::{
+    allow_undo, answer_draw, make_move, offer_draw, reject_pass, request_undo, submit_move,
+};
+
+/// Returns a game where black is about to push off a sixth white ball.
+fn almost_won() -> Abalone {
+    Abalone::from_fen("w4/bb4/bbb4/1bb5/2bb5/8/7/2www1/wwwww w").unwrap()
+}
+
+#[test]
+fn applied_move_outcome() {
+    let mut game = almost_won();
+
+    let mov = game.check_move([(8, 8).into(); 2], Dir::NegY).unwrap();
+    let msg = submit_move(&mut game, mov);
+    assert!(matches!(msg, ServerMsg::AppliedMove(m, _, None) if m == mov));
+    assert_eq!(game.turn, Color::White.opposite());
+
+    let mov = game.check_move([(2, 2).into(); 2], Dir::NegZ).unwrap();
+    let msg = submit_move(&mut game, mov);
+    let expected = Some(Outcome::Win(Color::Black));
+    let ServerMsg::AppliedMove(m, move_outcome, outcome) = msg else {
+        panic!("expected an applied move: {msg:?}");
+    };
+    assert_eq!(m, mov);
+    assert_eq!(move_outcome.captured, Some(Color::White));
+    assert_eq!(outcome, expected);
+}
+
+#[test]
+fn pass_rejected() {
+    let game = Abalone::new();
+    assert!(game.must_move());
+    assert!(matches!(reject_pass(&game), ServerMsg::Error(_)));
+}
+
+#[test]
+fn applied_move_animation() {
+    // with reserves away from the push, so the game isn't won already
+    let mut game = Abalone::from_fen("5/6/2b4/2bbbbbb/2w1bbbbb/1w6/w6/1wwwww/wwwww w").unwrap();
+
+    // 3 white balls push 2 black ones
+    let mov = game.check_move([(2, 6).into(); 2], Dir::NegY).unwrap();
+    let expected_mov = Move::PushedAway {
+        first: (2, 6).into(),
+        last: (2, 2).into(),
+    };
+    assert_eq!(mov, expected_mov);
+
+    let msg = submit_move(&mut game, mov);
+    let ServerMsg::AppliedMove(_, move_outcome, _) = msg else {
+        panic!("expected an applied move: {msg:?}");
+    };
+    let transitions: Vec<_> = [(6, 5), (5, 4), (4, 3), (3, 2), (2, 1)]
+        .into_iter()
+        .map(|(from, to)| ((2, from).into(), (2, to).into()))
+        .collect();
+    assert_eq!(move_outcome.transitions, transitions);
+    let changes = vec![
+        ((2, 6).into(), Some(Color::White), None),
+        ((2, 3).into(), Some(Color::Black), Some(Color::White)),
+        ((2, 1).into(), None, Some(Color::Black)),
+    ];
+    assert_eq!(move_outcome.delta.changes, changes);
+    assert_eq!(move_outcome.captured, None);
+}
+
+#[test]
+fn takeback() {
+    let mut game = Abalone::new();
+    let before = game.clone();
+    let mov = game.check_move([(4, 6).into(); 2], Dir::NegZ).unwrap();
+    submit_move(&mut game, mov);
+
+    // white requests to take back their move, black allows it
+    assert_eq!(request_undo(&game, Color::White), Ok(()));
+    assert!(matches!(
+        allow_undo(&mut game.clone(), Color::White, true),
+        ServerMsg::Error(_)
+    ));
+    assert!(matches!(
+        allow_undo(&mut game.clone(), Color::Black, false),
+        ServerMsg::Error(_)
+    ));
+    let msg = allow_undo(&mut game, Color::Black, true);
+    let ServerMsg::UndoneMove(m, delta, turn) = msg else {
+        panic!("expected an undone move: {msg:?}");
+    };
+    assert_eq!(m, mov);
+    assert_eq!(turn, Color::White);
+    let changes = vec![
+        ((4, 6).into(), None, Some(Color::White)),
+        ((3, 5).into(), Some(Color::White), None),
+    ];
+    assert_eq!(delta.changes, changes);
+    assert_eq!(game.balls, before.balls);
+    assert_eq!(game.turn, before.turn);
+
+    // nothing to take back at the start of the game
+    let game = Abalone::new();
+    assert!(request_undo(&game, Color::Black).is_err());
+    assert!(matches!(
+        allow_undo(&mut game.clone(), Color::White, true),
+        ServerMsg::Error(_)
+    ));
+}
+
+#[test]
+fn draw_offer() {
+    let mut game = Abalone::new();
+    assert_eq!(offer_draw(&game), Ok(()));
+
+    // white offers a draw, which black declines
+    let offer = Some(Color::White);
+    assert!(matches!(
+        answer_draw(&mut game, offer, Color::White, true),
+        ServerMsg::Error(_)
+    ));
+    let msg = answer_draw(&mut game, offer, Color::Black, false);
+    assert!(matches!(msg, ServerMsg::DrawDeclined));
+    assert_eq!(game.outcome(), None);
+
+    // black accepts the next offer
+    let msg = answer_draw(&mut game, offer, Color::Black, true);
+    assert!(matches!(msg, ServerMsg::DrawAgreed));
+    let expected = Some(Outcome::Draw(DrawReason::Agreement));
+    assert_eq!(game.outcome(), expected);
+    assert!(offer_draw(&game).is_err());
+}
+
+#[test]
+fn move_after_draw() {
+    let mut game = Abalone::new();
+    let selection = [(4, 6).into(); 2];
+    let msg = answer_draw(&mut game, Some(Color::Black), Color::White, true);
+    assert!(matches!(msg, ServerMsg::DrawAgreed));
+
+    let before = game.clone();
+    let msg = make_move(&mut game, Color::White, selection, Dir::NegZ);
+    assert!(matches!(msg, ServerMsg::Error(_)));
+    assert_eq!(game, before);
+
+    // a draw by the move limit ends the game as well
+    let mut game = Abalone::new();
+    game.rules.move_limit = Some(0);
+    let msg = make_move(&mut game, Color::White, selection, Dir::NegZ);
+    assert!(matches!(msg, ServerMsg::Error(_)));
+    assert_eq!(game.ply(), 0);
+}